@@ -1,47 +1,40 @@
 use crate::error::Error;
-use crate::molecules::{new_data, new_witness, PlcAuthorization};
+use crate::molecules::{load_witness, new_data, new_witness, DidWeb5Witness};
 use alloc::vec::Vec;
 use ckb_did_plc_utils::{
-    operation::{parse_local_id, validate_operation_history},
+    authorization::local_id_authorization_message,
+    cid::Cid,
+    did::Web5Did,
+    error::Error as UtilsError,
+    local_id::{LocalIdMethod, PlcMethod},
     reader::validate_cbor_format,
 };
 use ckb_std::error::SysError;
 use ckb_std::syscalls::load_cell;
-use ckb_std::{ckb_constants::Source, high_level::load_tx_hash, type_id::check_type_id};
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_script, load_tx_hash},
+    type_id::check_type_id,
+};
 use molecule::lazy_reader::Cursor;
 
 fn mint() -> Result<(), Error> {
     let data = new_data(0, Source::GroupOutput)?;
     // validate cbor format
-    validate_cbor_format(data.document()?)?;
+    validate_cbor_format(data.document)?;
 
-    let local_id = data.local_id()?;
-    // Allow empty local ID - this indicates the cell has no associated did:plc
-    // and can be minted without requiring did:plc authorization
-    if local_id.is_none() {
+    // Allow empty local ID - this indicates the cell has no associated local ID
+    // and can be minted without requiring its authorization
+    let Some(local_id) = data.local_id else {
+        if data.plc_head.is_some() {
+            return Err(Error::MismatchedPlcHead);
+        }
         return Ok(());
-    }
-    let local_id: Vec<u8> = local_id.unwrap().try_into()?;
+    };
 
     let witness = new_witness()?;
-    let auth: PlcAuthorization = witness.local_id_authorization()?;
-
-    let binary_did = parse_local_id(&local_id)?;
-    // History contains DID operations which can be very large. Using Cursor for lazy reading
-    // to avoid loading the entire operation history into memory at once.
-    let history: Vec<Cursor> = auth.history()?.into_iter().collect();
-    let final_sig: Vec<u8> = auth.sig()?.try_into()?;
-    let rotation_key_indices: Vec<u8> = auth.rotation_key_indices()?.try_into()?;
-    let rotation_key_indices: Vec<usize> = rotation_key_indices
-        .into_iter()
-        .map(|e| e as usize)
-        .collect();
-    let msg = load_tx_hash()?;
-    validate_operation_history(&binary_did, history, rotation_key_indices, &msg, &final_sig)?;
-    #[cfg(feature = "enable_log")]
-    log::info!("validate operation history successfully");
-
-    Ok(())
+    let head = verify_local_id(&local_id, &witness, None)?;
+    check_plc_head(data.plc_head, head)
 }
 
 fn update() -> Result<(), Error> {
@@ -49,23 +42,74 @@ fn update() -> Result<(), Error> {
     let cur_data = new_data(0, Source::GroupOutput)?;
 
     // validate formats of document
-    validate_cbor_format(cur_data.document()?)?;
-    validate_cbor_format(prev_data.document()?)?;
+    validate_cbor_format(cur_data.document)?;
+    validate_cbor_format(prev_data.document)?;
+
+    if prev_data.local_id != cur_data.local_id {
+        return Err(Error::MismatchedFrom);
+    }
+    // An update with an authorization verifies the did:plc operations since the last
+    // verified one, whose CID is kept in `plc_head`, or the history from genesis, e.g.
+    // after a recovery cancelled `plc_head`. Without, `plc_head` can't change.
+    let (Some(local_id), Some(witness)) = (&cur_data.local_id, load_witness()?) else {
+        if prev_data.plc_head != cur_data.plc_head {
+            return Err(Error::MismatchedPlcHead);
+        }
+        return Ok(());
+    };
+    let checkpoint = prev_data
+        .plc_head
+        .map(|head| Cid::from_bytes(&head))
+        .transpose()?;
+    let head = verify_local_id(local_id, &witness, checkpoint)?;
+    check_plc_head(cur_data.plc_head, head)
+}
 
-    let prev_from: Vec<Vec<u8>> = prev_data
-        .local_id()?
-        .into_iter()
-        .map(|c| c.try_into().map_err(|_| Error::Molecule))
-        .collect::<Result<Vec<_>, _>>()?;
-    let cur_from: Vec<Vec<u8>> = cur_data
-        .local_id()?
-        .into_iter()
-        .map(|c| c.try_into().map_err(|_| Error::Molecule))
-        .collect::<Result<Vec<_>, _>>()?;
-    if prev_from != cur_from {
-        Err(Error::MismatchedFrom)
+// Dispatches by the method of the local ID. Returns the head CID for did:plc.
+fn verify_local_id(
+    local_id: &[u8],
+    witness: &DidWeb5Witness,
+    checkpoint: Option<Cid>,
+) -> Result<Option<Cid>, Error> {
+    let local_id = core::str::from_utf8(local_id).map_err(|_| UtilsError::InvalidDidFormat)?;
+    if local_id.starts_with(PlcMethod::PREFIX) {
+        // History contains DID operations which can be very large. The method only slices
+        // them from the witness for lazy reading, instead of loading the entire history.
+        let auth = witness.local_id_authorization()?.cursor;
+        let head = verify::<PlcMethod>(local_id, &auth, checkpoint)?;
+        #[cfg(feature = "enable_log")]
+        log::info!("validate operation history successfully");
+        Ok(Some(head))
     } else {
-        Ok(())
+        Err(UtilsError::InvalidDidFormat.into())
+    }
+}
+
+// Verifies the authorization of the local ID over this transaction
+fn verify<M: LocalIdMethod>(
+    local_id: &str,
+    auth: &Cursor,
+    verified: Option<M::Verified>,
+) -> Result<M::Verified, Error>
+where
+    Error: From<M::Error>,
+{
+    let id = M::parse_id(local_id)?;
+    let auth = M::authorization(auth, verified)?;
+    // The type ID is checked by `check_type_id` to be the first 20 bytes of the args
+    let web5_did = Web5Did::from_type_id_args(&load_script()?.args().raw_data())?;
+    let msg = local_id_authorization_message(local_id, web5_did.as_bytes(), &load_tx_hash()?);
+    Ok(M::verify(&id, auth, &msg)?)
+}
+
+// `plc_head` of the output must be the verified head when set. It may be left empty, as V1
+// cell data has none: the next update then has no checkpoint and verifies the history
+// from genesis again, which costs more cycles but is as safe.
+fn check_plc_head(plc_head: Option<Vec<u8>>, head: Option<Cid>) -> Result<(), Error> {
+    match (plc_head, head) {
+        (None, _) => Ok(()),
+        (Some(plc_head), Some(head)) if plc_head == head.to_bytes() => Ok(()),
+        _ => Err(Error::MismatchedPlcHead),
     }
 }
 
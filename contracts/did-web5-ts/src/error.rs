@@ -1,4 +1,4 @@
-use ckb_did_plc_utils::error::Error as UtilsError;
+use ckb_did_plc_utils::error::{Error as UtilsError, HistoryError};
 use ckb_std::error::SysError;
 use core::fmt::Display;
 use molecule::lazy_reader::Error as MoleculeError;
@@ -7,9 +7,12 @@ use molecule::lazy_reader::Error as MoleculeError;
 pub enum Error {
     Syscall(SysError),
     Utils(UtilsError),
+    // an error of the operation history, with its position for the log
+    History(HistoryError),
     Molecule,
     InvalidDocumentCbor,
     MismatchedFrom,
+    MismatchedPlcHead,
 }
 
 impl Display for Error {
@@ -32,6 +35,12 @@ impl From<UtilsError> for Error {
     }
 }
 
+impl From<HistoryError> for Error {
+    fn from(e: HistoryError) -> Self {
+        Error::History(e)
+    }
+}
+
 impl From<MoleculeError> for Error {
     fn from(_: MoleculeError) -> Self {
         Error::Molecule
@@ -51,8 +60,9 @@ impl Error {
                 SysError::TypeIDError => 26,
                 _ => 27,
             },
-            // crate ckb-did-plc-utils error starts from 31
-            Error::Utils(e) => match e {
+            // crate ckb-did-plc-utils error starts from 31, continues from 61
+            // once 31..=50 are used up
+            Error::Utils(e) | Error::History(HistoryError { error: e, .. }) => match e {
                 UtilsError::InvalidOperation => 31,
                 UtilsError::RotationKeysDecodeError => 32,
                 UtilsError::InvalidKey => 33,
@@ -69,11 +79,36 @@ impl Error {
                 UtilsError::MoleculeError(_) => 44,
                 UtilsError::InvalidCbor => 45,
                 UtilsError::InvalidDidFormat => 46,
+                UtilsError::DidTombstoned => 47,
+                UtilsError::NonCanonicalCbor => 48,
+                UtilsError::MissingField => 49,
+                UtilsError::UnexpectedField => 50,
+                UtilsError::InvalidRotationKeysCount => 61,
+                UtilsError::DuplicatedRotationKey => 62,
+                UtilsError::InvalidVerificationMethods => 63,
+                UtilsError::InvalidAlsoKnownAs => 64,
+                UtilsError::InvalidServices => 65,
+                UtilsError::InvalidPrevType => 66,
+                UtilsError::HighSSignature => 67,
+                UtilsError::InvalidCid => 68,
+                UtilsError::OperationTooLarge => 70,
+                UtilsError::HistoryTooLong => 71,
+                UtilsError::TooManyRotationKeys => 72,
+                UtilsError::TooManyServices => 73,
+                UtilsError::TooManyAlsoKnownAs => 74,
+                UtilsError::CheckpointMismatched => 75,
+                // off-chain only, e.g. the fork resolution and the JSON parser, which this
+                // script never calls
+                UtilsError::MisorderedOperation
+                | UtilsError::LateRecovery
+                | UtilsError::InvalidTimestamp
+                | UtilsError::InvalidJson => 69,
             },
-            // this script error starts from 51
+            // this script error starts from 51, up to 60
             Error::Molecule => 51,
             Error::InvalidDocumentCbor => 52,
             Error::MismatchedFrom => 53,
+            Error::MismatchedPlcHead => 54,
         }
     }
 }
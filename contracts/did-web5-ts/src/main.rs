@@ -19,7 +19,10 @@ pub fn program_entry() -> i8 {
         Ok(_) => 0,
         Err(e) => {
             #[cfg(feature = "enable_log")]
-            log::error!("error: {:?}", e);
+            match &e {
+                error::Error::History(e) => log::error!("error: {}", e),
+                _ => log::error!("error: {:?}", e),
+            }
             e.error_code()
         }
     }
@@ -77,15 +77,26 @@ impl From<DataReader> for Cursor {
     }
 }
 
-pub fn new_data(index: usize, source: Source) -> Result<DidWeb5DataV1, Error> {
+// The cell data of any version, decoded
+pub struct CellData {
+    pub document: Cursor,
+    pub local_id: Option<Vec<u8>>,
+    // since V2
+    pub plc_head: Option<Vec<u8>>,
+}
+
+pub fn new_data(index: usize, source: Source) -> Result<CellData, Error> {
     let reader = DataReader::new(index, source);
     let cursor: Cursor = reader.into();
     let data = DidWeb5Data::try_from(cursor)?;
     data.verify(false)?;
 
-    let DidWeb5Data::DidWeb5DataV1(data) = data;
-    let doc: Vec<u8> = data
-        .document()?
+    let (document, local_id, plc_head) = match data {
+        DidWeb5Data::DidWeb5DataV1(data) => (data.document()?, data.local_id()?, None),
+        DidWeb5Data::DidWeb5DataV2(data) => (data.document()?, data.local_id()?, data.plc_head()?),
+    };
+    let doc: Vec<u8> = document
+        .clone()
         .try_into()
         .map_err(|_| Error::InvalidDocumentCbor)?;
 
@@ -93,7 +104,11 @@ pub fn new_data(index: usize, source: Source) -> Result<DidWeb5DataV1, Error> {
     let mut reader = SliceReader::new(&doc);
     let _ = Value::decode(&mut reader).map_err(|_| Error::InvalidDocumentCbor)?;
 
-    Ok(data)
+    Ok(CellData {
+        document,
+        local_id: local_id.map(|c| c.try_into()).transpose()?,
+        plc_head: plc_head.map(|c| c.try_into()).transpose()?,
+    })
 }
 
 pub struct WitnessArgsReader {
@@ -139,10 +154,22 @@ pub fn new_witness_args(index: usize, source: Source) -> Result<witness::Witness
 }
 
 pub fn new_witness() -> Result<witness::DidWeb5Witness, Error> {
+    load_witness()?.ok_or(Error::Molecule)
+}
+
+// The witness is optional on update: `None` without witness or without `output_type`.
+pub fn load_witness() -> Result<Option<witness::DidWeb5Witness>, Error> {
+    let buf = &mut [];
+    match syscalls::load_witness(buf, 0, 0, Source::GroupOutput) {
+        Ok(_) | Err(SysError::LengthNotEnough(_)) => {}
+        Err(SysError::IndexOutOfBound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
     let witness_args = new_witness_args(0, Source::GroupOutput)?;
-    let output_type = witness_args.output_type()?;
-    let output_type = output_type.ok_or(Error::Molecule)?;
+    let Some(output_type) = witness_args.output_type()? else {
+        return Ok(None);
+    };
     let witness = DidWeb5Witness::from(output_type);
     witness.verify(false)?;
-    Ok(witness)
+    Ok(Some(witness))
 }
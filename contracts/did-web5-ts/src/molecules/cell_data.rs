@@ -173,6 +173,14 @@ impl From<Cursor> for StringOpt {
         Self { cursor }
     }
 }
+pub struct BytesOpt {
+    pub cursor: Cursor,
+}
+impl From<Cursor> for BytesOpt {
+    fn from(cursor: Cursor) -> Self {
+        Self { cursor }
+    }
+}
 #[derive(Clone)]
 pub struct DidWeb5DataV1 {
     pub cursor: Cursor,
@@ -205,8 +213,52 @@ impl DidWeb5DataV1 {
         Ok(())
     }
 }
+#[derive(Clone)]
+pub struct DidWeb5DataV2 {
+    pub cursor: Cursor,
+}
+impl From<Cursor> for DidWeb5DataV2 {
+    fn from(cursor: Cursor) -> Self {
+        DidWeb5DataV2 { cursor }
+    }
+}
+impl DidWeb5DataV2 {
+    pub fn document(&self) -> Result<Cursor, Error> {
+        let cur = self.cursor.table_slice_by_index(0usize)?;
+        cur.convert_to_rawbytes()
+    }
+}
+impl DidWeb5DataV2 {
+    pub fn local_id(&self) -> Result<Option<Cursor>, Error> {
+        let cur = self.cursor.table_slice_by_index(1usize)?;
+        if cur.option_is_none() {
+            Ok(None)
+        } else {
+            let cur = cur.convert_to_rawbytes()?;
+            Ok(Some(cur.into()))
+        }
+    }
+}
+impl DidWeb5DataV2 {
+    pub fn plc_head(&self) -> Result<Option<Cursor>, Error> {
+        let cur = self.cursor.table_slice_by_index(2usize)?;
+        if cur.option_is_none() {
+            Ok(None)
+        } else {
+            let cur = cur.convert_to_rawbytes()?;
+            Ok(Some(cur.into()))
+        }
+    }
+}
+impl DidWeb5DataV2 {
+    pub fn verify(&self, compatible: bool) -> Result<(), Error> {
+        self.cursor.verify_table(3usize, compatible)?;
+        Ok(())
+    }
+}
 pub enum DidWeb5Data {
     DidWeb5DataV1(DidWeb5DataV1),
+    DidWeb5DataV2(DidWeb5DataV2),
 }
 impl TryFrom<Cursor> for DidWeb5Data {
     type Error = Error;
@@ -217,6 +269,7 @@ impl TryFrom<Cursor> for DidWeb5Data {
         cur.sub_size(NUMBER_SIZE)?;
         match item.item_id {
             0usize => Ok(Self::DidWeb5DataV1(cur.into())),
+            1usize => Ok(Self::DidWeb5DataV2(cur.into())),
             _ => Err(Error::UnknownItem),
         }
     }
@@ -228,6 +281,10 @@ impl DidWeb5Data {
                 v.verify(compatible)?;
                 Ok(())
             }
+            Self::DidWeb5DataV2(v) => {
+                v.verify(compatible)?;
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,48 @@
+// The message signed by a rotation key to authorize a did:plc on chain, i.e.
+// `PlcAuthorization.sig` of the on-chain script.
+//
+// Signing the raw transaction hash would make the signature valid for any other
+// protocol signing raw 32-byte hashes with the same key. Instead, the signed message is
+// a text, prefixed with a string of its own, which names what is authorized and can be
+// displayed by wallets:
+//
+// ```text
+// did:web5 local id authorization
+// <local ID, e.g. did:plc:<base32 binary DID>>
+// did:web5:<base32 type ID>
+// tx hash: 0x<hex tx hash>
+// ```
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use base32::Alphabet;
+
+pub const AUTHORIZATION_PREFIX: &str = "did:web5 local id authorization";
+
+const BASE32: Alphabet = Alphabet::Rfc4648Lower { padding: false };
+
+/// Builds the message to sign for the final authorization, see the module comment.
+/// `type_id` is the type ID in the args of the did:web5 type script, `tx_hash` the hash
+/// of the transaction.
+pub fn authorization_message(binary_did: &[u8], type_id: &[u8], tx_hash: &[u8]) -> Vec<u8> {
+    let local_id = format!("did:plc:{}", base32::encode(BASE32, binary_did));
+    local_id_authorization_message(&local_id, type_id, tx_hash)
+}
+
+/// Same as `authorization_message`, for a local ID of any method, e.g. `did:plc:...`.
+pub fn local_id_authorization_message(local_id: &str, type_id: &[u8], tx_hash: &[u8]) -> Vec<u8> {
+    let mut hex = String::with_capacity(tx_hash.len() * 2);
+    for byte in tx_hash {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    format!(
+        "{}\n{}\ndid:web5:{}\ntx hash: 0x{}",
+        AUTHORIZATION_PREFIX,
+        local_id,
+        base32::encode(BASE32, type_id),
+        hex
+    )
+    .into_bytes()
+}
@@ -0,0 +1,168 @@
+// Builds DID PLC operations in the same DAG-CBOR form as `@did-plc/lib`, so the
+// results can be verified by `validate_2_operations` and friends directly.
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use cbor4ii::core::Value;
+
+use crate::cid::Cid;
+use crate::dag_cbor::{dag_cbor_map, sort_map_keys};
+use crate::error::Error;
+use crate::operation::{Operation, OperationType, Service};
+use crate::signer::PrivateKey;
+
+#[derive(Debug, Clone)]
+pub struct OperationBuilder {
+    r#type: OperationType,
+    rotation_keys: Vec<String>,
+    verification_methods: BTreeMap<String, String>,
+    also_known_as: Vec<String>,
+    services: BTreeMap<String, Service>,
+    prev: Option<Cid>,
+}
+
+impl Default for OperationBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OperationBuilder {
+    /// Starts a genesis operation (`prev` is null).
+    pub fn new() -> Self {
+        OperationBuilder {
+            r#type: OperationType::PlcOperation,
+            rotation_keys: vec![],
+            verification_methods: BTreeMap::new(),
+            also_known_as: vec![],
+            services: BTreeMap::new(),
+            prev: None,
+        }
+    }
+
+    /// Starts an update operation on top of `prev_op`. All document data is copied
+    /// from `prev_op` (normalized if it's a legacy operation) and `prev` is set to
+    /// its CID.
+    pub fn update(prev_op: &Operation) -> Result<Self, Error> {
+        if prev_op.is_tombstone() {
+            return Err(Error::DidTombstoned);
+        }
+        let normalized = prev_op.normalize()?;
+        Ok(OperationBuilder {
+            r#type: OperationType::PlcOperation,
+            rotation_keys: normalized
+                .get_rotation_keys()?
+                .iter()
+                .map(|key| key.to_did_key())
+                .collect(),
+            verification_methods: normalized.get_verification_methods()?,
+            also_known_as: normalized.get_also_known_as()?,
+            services: normalized.get_services()?,
+            prev: Some(prev_op.generate_cid()?),
+        })
+    }
+
+    /// Starts a tombstone operation which deactivates the DID after `prev_op`.
+    pub fn tombstone(prev_op: &Operation) -> Result<Self, Error> {
+        if prev_op.is_tombstone() {
+            return Err(Error::DidTombstoned);
+        }
+        Ok(OperationBuilder {
+            r#type: OperationType::PlcTombstone,
+            prev: Some(prev_op.generate_cid()?),
+            ..Self::new()
+        })
+    }
+
+    /// Rotation keys as did:key strings, in order of priority.
+    pub fn rotation_keys(mut self, keys: Vec<String>) -> Self {
+        self.rotation_keys = keys;
+        self
+    }
+
+    pub fn verification_method(mut self, name: &str, did_key: &str) -> Self {
+        self.verification_methods
+            .insert(name.to_string(), did_key.to_string());
+        self
+    }
+
+    pub fn also_known_as(mut self, also_known_as: Vec<String>) -> Self {
+        self.also_known_as = also_known_as;
+        self
+    }
+
+    pub fn service(mut self, id: &str, r#type: &str, endpoint: &str) -> Self {
+        self.services.insert(
+            id.to_string(),
+            Service {
+                r#type: r#type.to_string(),
+                endpoint: endpoint.to_string(),
+            },
+        );
+        self
+    }
+
+    pub fn build_unsigned(&self) -> Result<Operation, Error> {
+        let prev = match &self.prev {
+            Some(prev) => Value::Text(prev.to_string()),
+            None => Value::Null,
+        };
+        let mut raw = vec![
+            (text("type"), text(self.r#type.as_str())),
+            (text("prev"), prev),
+        ];
+        if self.r#type == OperationType::PlcOperation {
+            if self.rotation_keys.is_empty() {
+                return Err(Error::InvalidRotationKeysCount);
+            }
+            let verification_methods = self
+                .verification_methods
+                .iter()
+                .map(|(name, key)| (text(name), text(key)))
+                .collect();
+            let services = self
+                .services
+                .iter()
+                .map(|(id, service)| {
+                    let service = vec![
+                        (text("type"), text(&service.r#type)),
+                        (text("endpoint"), text(&service.endpoint)),
+                    ];
+                    (text(id), dag_cbor_map(service))
+                })
+                .collect();
+            raw.push((
+                text("rotationKeys"),
+                Value::Array(self.rotation_keys.iter().map(|k| text(k)).collect()),
+            ));
+            raw.push((
+                text("verificationMethods"),
+                dag_cbor_map(verification_methods),
+            ));
+            raw.push((
+                text("alsoKnownAs"),
+                Value::Array(self.also_known_as.iter().map(|k| text(k)).collect()),
+            ));
+            raw.push((text("services"), dag_cbor_map(services)));
+        }
+        sort_map_keys(&mut raw);
+        Ok(Operation::from_raw(raw))
+    }
+
+    /// Builds the operation and signs it with `key`, which must be one of the rotation
+    /// keys of the previous operation (or of this operation for genesis).
+    pub fn sign(&self, key: &PrivateKey) -> Result<Operation, Error> {
+        let unsigned_op = self.build_unsigned()?;
+        let sig = key.sign_base64(&unsigned_op.to_vec()?)?;
+        let mut raw = unsigned_op.into_raw();
+        raw.push((text("sig"), Value::Text(sig)));
+        sort_map_keys(&mut raw);
+        Ok(Operation::from_raw(raw))
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
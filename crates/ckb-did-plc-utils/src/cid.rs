@@ -0,0 +1,95 @@
+// A minimal CID implementation, only the form used by did:plc is supported:
+// * CIDv1
+// * dag-cbor multicodec (code: 0x71)
+// * sha2-256 multihash (code: 0x12, length: 32)
+// * base32 multibase encoding (prefix: b) for the string form
+use alloc::string::String;
+use core::fmt::{self, Display};
+use core::str::FromStr;
+
+use base32::Alphabet;
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+
+pub const CID_VERSION: u8 = 0x01;
+pub const DAG_CBOR_CODEC: u8 = 0x71;
+pub const SHA2_256_CODE: u8 = 0x12;
+pub const SHA2_256_LENGTH: u8 = 0x20;
+const PREFIX: [u8; 4] = [CID_VERSION, DAG_CBOR_CODEC, SHA2_256_CODE, SHA2_256_LENGTH];
+/// Length of the binary form: 4 bytes prefix and 32 bytes digest
+pub const CID_LENGTH: usize = 36;
+const BASE32: Alphabet = Alphabet::Rfc4648Lower { padding: false };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cid {
+    digest: [u8; 32],
+}
+
+impl Cid {
+    pub fn new(digest: [u8; 32]) -> Self {
+        Cid { digest }
+    }
+
+    /// CID of DAG-CBOR encoded data
+    pub fn from_dag_cbor(data: &[u8]) -> Self {
+        Cid {
+            digest: Sha256::digest(data).into(),
+        }
+    }
+
+    /// Parses the binary form: `<version><codec><multihash code><multihash length><digest>`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != CID_LENGTH || bytes[..4] != PREFIX {
+            return Err(Error::InvalidCid);
+        }
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&bytes[4..]);
+        Ok(Cid { digest })
+    }
+
+    pub fn to_bytes(&self) -> [u8; CID_LENGTH] {
+        let mut bytes = [0u8; CID_LENGTH];
+        bytes[..4].copy_from_slice(&PREFIX);
+        bytes[4..].copy_from_slice(&self.digest);
+        bytes
+    }
+
+    pub fn version(&self) -> u8 {
+        CID_VERSION
+    }
+
+    pub fn codec(&self) -> u8 {
+        DAG_CBOR_CODEC
+    }
+
+    pub fn hash_code(&self) -> u8 {
+        SHA2_256_CODE
+    }
+
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+}
+
+impl FromStr for Cid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let b32 = s.strip_prefix('b').ok_or(Error::InvalidCid)?;
+        let bytes = base32::decode(BASE32, b32).ok_or(Error::InvalidCid)?;
+        let cid = Cid::from_bytes(&bytes)?;
+        // only the canonical (lowercase, no padding) string form is accepted
+        if base32::encode(BASE32, &bytes) != b32 {
+            return Err(Error::InvalidCid);
+        }
+        Ok(cid)
+    }
+}
+
+impl Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let b32: String = base32::encode(BASE32, &self.to_bytes());
+        write!(f, "b{}", b32)
+    }
+}
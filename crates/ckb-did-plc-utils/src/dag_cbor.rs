@@ -0,0 +1,158 @@
+// Strict DAG-CBOR checker.
+//
+// `cbor4ii` decodes any well-formed CBOR, but the CID of an operation is computed over
+// its re-encoded form. If the submitted bytes are not canonical DAG-CBOR, the CID
+// would not match the bytes. This checker walks the raw bytes and rejects anything
+// that doesn't follow https://ipld.io/specs/codecs/dag-cbor/spec/#strictness:
+// * integers and lengths use the shortest encoding
+// * no indefinite-length items
+// * map keys are text strings, sorted by length then bytewise, without duplicates
+// * no floats, no simple values other than false/true/null
+// * no tags other than 42 (CID)
+// * no trailing bytes after the top-level item
+use alloc::vec::Vec;
+use cbor4ii::core::Value;
+
+use crate::error::Error;
+
+pub(crate) const MAX_DEPTH: usize = 64;
+pub(crate) const CID_TAG: u64 = 42;
+
+pub fn validate_dag_cbor(buf: &[u8]) -> Result<(), Error> {
+    let mut checker = Checker { buf, pos: 0 };
+    checker.item(0)?;
+    if checker.pos != buf.len() {
+        return Err(Error::NonCanonicalCbor);
+    }
+    Ok(())
+}
+
+struct Checker<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Checker<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.pos.checked_add(len).ok_or(Error::InvalidCbor)?;
+        if end > self.buf.len() {
+            return Err(Error::InvalidCbor);
+        }
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    // returns (major type, additional info, argument)
+    fn header(&mut self) -> Result<(u8, u8, u64), Error> {
+        let byte = self.take(1)?[0];
+        let major = byte >> 5;
+        let info = byte & 0x1f;
+        let (arg, min) = match info {
+            0..=23 => (info as u64, 0),
+            24 => (self.take(1)?[0] as u64, 24),
+            25 => (
+                u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+                0x100,
+            ),
+            26 => (
+                u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+                0x1_0000,
+            ),
+            27 => (
+                u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+                0x1_0000_0000,
+            ),
+            // indefinite length
+            31 => return Err(Error::NonCanonicalCbor),
+            _ => return Err(Error::InvalidCbor),
+        };
+        // major type 7 carries floats/simple values instead of integers
+        if major != 7 && arg < min {
+            return Err(Error::NonCanonicalCbor);
+        }
+        Ok((major, info, arg))
+    }
+
+    fn len(arg: u64) -> Result<usize, Error> {
+        usize::try_from(arg).map_err(|_| Error::InvalidCbor)
+    }
+
+    fn item(&mut self, depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidCbor);
+        }
+        let (major, info, arg) = self.header()?;
+        match major {
+            // unsigned and negative integers
+            0 | 1 => {}
+            // bytes
+            2 => {
+                self.take(Self::len(arg)?)?;
+            }
+            // text
+            3 => {
+                let text = self.take(Self::len(arg)?)?;
+                core::str::from_utf8(text).map_err(|_| Error::InvalidCbor)?;
+            }
+            // array
+            4 => {
+                for _ in 0..arg {
+                    self.item(depth + 1)?;
+                }
+            }
+            // map
+            5 => {
+                let mut last_key: Option<&[u8]> = None;
+                for _ in 0..arg {
+                    let (key_major, _, key_len) = self.header()?;
+                    if key_major != 3 {
+                        return Err(Error::NonCanonicalCbor);
+                    }
+                    let key = self.take(Self::len(key_len)?)?;
+                    core::str::from_utf8(key).map_err(|_| Error::InvalidCbor)?;
+                    if let Some(last_key) = last_key {
+                        // strictly increasing, which also rejects duplicated keys
+                        if (last_key.len(), last_key) >= (key.len(), key) {
+                            return Err(Error::NonCanonicalCbor);
+                        }
+                    }
+                    last_key = Some(key);
+                    self.item(depth + 1)?;
+                }
+            }
+            // tag
+            6 => {
+                if arg != CID_TAG {
+                    return Err(Error::NonCanonicalCbor);
+                }
+                // a CID: bytes starting with the identity multibase prefix 0x00
+                let (major, _, len) = self.header()?;
+                if major != 2 || self.take(Self::len(len)?)?.first() != Some(&0) {
+                    return Err(Error::NonCanonicalCbor);
+                }
+            }
+            // false, true, null
+            _ => {
+                if !matches!(info, 20..=22) {
+                    return Err(Error::NonCanonicalCbor);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Builds a map with its keys sorted in DAG-CBOR order
+pub(crate) fn dag_cbor_map(mut pairs: Vec<(Value, Value)>) -> Value {
+    sort_map_keys(&mut pairs);
+    Value::Map(pairs)
+}
+
+// DAG-CBOR map keys are sorted by length first, then bytewise
+pub(crate) fn sort_map_keys(pairs: &mut [(Value, Value)]) {
+    pairs.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Value::Text(a), Value::Text(b)) => (a.len(), a.as_bytes()).cmp(&(b.len(), b.as_bytes())),
+        _ => core::cmp::Ordering::Equal,
+    });
+}
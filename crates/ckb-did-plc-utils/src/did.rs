@@ -0,0 +1,114 @@
+// The two DID methods involved:
+// * did:plc, the local ID: "did:plc:" followed by 24 base32 characters, the first 15
+//   bytes of the SHA-256 hash of the genesis operation
+// * did:web5, the cell: "did:web5:" followed by 32 base32 characters, the 20-byte type
+//   ID in the args of the did:web5 type script, see `check_type_id(0, 20)`
+//
+// Only the canonical form is accepted: lowercase RFC 4648 base32 without padding, of the
+// exact length.
+use alloc::string::String;
+use core::fmt::{self, Display};
+use core::str::FromStr;
+
+use base32::Alphabet;
+
+use crate::error::Error;
+
+pub const PLC_DID_PREFIX: &str = "did:plc:";
+pub const WEB5_DID_PREFIX: &str = "did:web5:";
+/// Length of the binary form of a did:plc
+pub const PLC_DID_LENGTH: usize = 15;
+/// Length of the binary form of a did:web5, the type ID
+pub const WEB5_DID_LENGTH: usize = 20;
+const BASE32: Alphabet = Alphabet::Rfc4648Lower { padding: false };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PlcDid([u8; PLC_DID_LENGTH]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Web5Did([u8; WEB5_DID_LENGTH]);
+
+impl PlcDid {
+    pub fn new(bytes: [u8; PLC_DID_LENGTH]) -> Self {
+        PlcDid(bytes)
+    }
+
+    /// From the binary form, e.g. `Operation::get_binary_did`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(PlcDid(
+            bytes.try_into().map_err(|_| Error::InvalidDidFormat)?,
+        ))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; PLC_DID_LENGTH] {
+        &self.0
+    }
+}
+
+impl Web5Did {
+    pub fn new(type_id: [u8; WEB5_DID_LENGTH]) -> Self {
+        Web5Did(type_id)
+    }
+
+    /// From the binary form, the type ID itself
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Web5Did(
+            bytes.try_into().map_err(|_| Error::InvalidDidFormat)?,
+        ))
+    }
+
+    /// From the args of the did:web5 type script, which start with the type ID
+    pub fn from_type_id_args(args: &[u8]) -> Result<Self, Error> {
+        let type_id = args.get(..WEB5_DID_LENGTH).ok_or(Error::InvalidDidFormat)?;
+        Self::from_bytes(type_id)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; WEB5_DID_LENGTH] {
+        &self.0
+    }
+}
+
+impl FromStr for PlcDid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(PlcDid(decode(s, PLC_DID_PREFIX)?))
+    }
+}
+
+impl FromStr for Web5Did {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(Web5Did(decode(s, WEB5_DID_PREFIX)?))
+    }
+}
+
+impl Display for PlcDid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let b32: String = base32::encode(BASE32, &self.0);
+        write!(f, "{}{}", PLC_DID_PREFIX, b32)
+    }
+}
+
+impl Display for Web5Did {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let b32: String = base32::encode(BASE32, &self.0);
+        write!(f, "{}{}", WEB5_DID_PREFIX, b32)
+    }
+}
+
+// Both lengths are multiples of 5 bytes, so there are no padding bits to check: the
+// exact length and the alphabet make the string form canonical.
+fn decode<const N: usize>(s: &str, prefix: &str) -> Result<[u8; N], Error> {
+    let b32 = s.strip_prefix(prefix).ok_or(Error::InvalidDidFormat)?;
+    if b32.len() != N * 8 / 5
+        || !b32
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || (b'2'..=b'7').contains(&c))
+    {
+        return Err(Error::InvalidDidFormat);
+    }
+    let bytes = base32::decode(BASE32, b32).ok_or(Error::InvalidDidFormat)?;
+    bytes.try_into().map_err(|_| Error::InvalidDidFormat)
+}
@@ -0,0 +1,284 @@
+// What an operation changes compared to the previous one, for display and audit logs.
+//
+// Both operations are normalized first, so a legacy `create` operation compares like
+// the equivalent `plc_operation`: its handle is in `alsoKnownAs`, its service in
+// `services` and its signing key in `verificationMethods`.
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::json::{write_json_array, write_json_object, write_json_string};
+use crate::operation::{Operation, Service};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The operation is a tombstone, the DID is deactivated
+    Tombstoned,
+    /// A rotation key (did:key) is added
+    RotationKeyAdded(String),
+    RotationKeyRemoved(String),
+    /// The rotation keys kept are in another order of priority
+    RotationKeysReordered {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    /// A verification method (e.g. "atproto") is added, with its did:key
+    VerificationMethodAdded {
+        id: String,
+        key: String,
+    },
+    VerificationMethodRemoved {
+        id: String,
+        key: String,
+    },
+    /// The key of a verification method, e.g. the atproto signing key, is replaced
+    VerificationMethodChanged {
+        id: String,
+        from: String,
+        to: String,
+    },
+    /// An `alsoKnownAs` entry (e.g. "at://alice.example.com") is added
+    AlsoKnownAsAdded(String),
+    AlsoKnownAsRemoved(String),
+    /// The entries kept are in another order, the first one is the handle
+    AlsoKnownAsReordered {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    /// A service (e.g. "atproto_pds") is added
+    ServiceAdded {
+        id: String,
+        service: Service,
+    },
+    ServiceRemoved {
+        id: String,
+        service: Service,
+    },
+    /// The type or the endpoint of a service, e.g. the PDS endpoint, is replaced
+    ServiceChanged {
+        id: String,
+        from: Service,
+        to: Service,
+    },
+}
+
+/// Lists what `cur` changes compared to `prev`, the operation before it. Only the
+/// document data is compared, not `prev` or `sig`: the order of the list is rotation
+/// keys, verification methods, `alsoKnownAs`, then services. A tombstone gives
+/// `[Change::Tombstoned]` only, nothing can follow a tombstone: `Error::DidTombstoned`.
+pub fn diff_operations(prev: &Operation, cur: &Operation) -> Result<Vec<Change>, Error> {
+    if prev.is_tombstone() {
+        return Err(Error::DidTombstoned);
+    }
+    if cur.is_tombstone() {
+        return Ok(vec![Change::Tombstoned]);
+    }
+    let prev = prev.normalize()?;
+    let cur = cur.normalize()?;
+    let mut changes = vec![];
+
+    let rotation_keys = |op: &Operation| -> Result<Vec<String>, Error> {
+        Ok(op
+            .get_rotation_keys()?
+            .iter()
+            .map(|key| key.to_did_key())
+            .collect())
+    };
+    diff_list(
+        &rotation_keys(&prev)?,
+        &rotation_keys(&cur)?,
+        &mut changes,
+        Change::RotationKeyAdded,
+        Change::RotationKeyRemoved,
+        |from, to| Change::RotationKeysReordered { from, to },
+    );
+    diff_map(
+        &prev.get_verification_methods()?,
+        &cur.get_verification_methods()?,
+        &mut changes,
+        |id, key| Change::VerificationMethodAdded { id, key },
+        |id, key| Change::VerificationMethodRemoved { id, key },
+        |id, from, to| Change::VerificationMethodChanged { id, from, to },
+    );
+    diff_list(
+        &prev.get_also_known_as()?,
+        &cur.get_also_known_as()?,
+        &mut changes,
+        Change::AlsoKnownAsAdded,
+        Change::AlsoKnownAsRemoved,
+        |from, to| Change::AlsoKnownAsReordered { from, to },
+    );
+    diff_map(
+        &prev.get_services()?,
+        &cur.get_services()?,
+        &mut changes,
+        |id, service| Change::ServiceAdded { id, service },
+        |id, service| Change::ServiceRemoved { id, service },
+        |id, from, to| Change::ServiceChanged { id, from, to },
+    );
+    Ok(changes)
+}
+
+// Removed items, added items, then whether the items in both lists kept their order
+fn diff_list(
+    prev: &[String],
+    cur: &[String],
+    changes: &mut Vec<Change>,
+    added: impl Fn(String) -> Change,
+    removed: impl Fn(String) -> Change,
+    reordered: impl Fn(Vec<String>, Vec<String>) -> Change,
+) {
+    for item in prev.iter().filter(|item| !cur.contains(item)) {
+        changes.push(removed(item.clone()));
+    }
+    for item in cur.iter().filter(|item| !prev.contains(item)) {
+        changes.push(added(item.clone()));
+    }
+    let kept_prev = prev.iter().filter(|item| cur.contains(item));
+    let kept_cur = cur.iter().filter(|item| prev.contains(item));
+    if !kept_prev.eq(kept_cur) {
+        changes.push(reordered(prev.to_vec(), cur.to_vec()));
+    }
+}
+
+fn diff_map<T: Clone + PartialEq>(
+    prev: &BTreeMap<String, T>,
+    cur: &BTreeMap<String, T>,
+    changes: &mut Vec<Change>,
+    added: impl Fn(String, T) -> Change,
+    removed: impl Fn(String, T) -> Change,
+    changed: impl Fn(String, T, T) -> Change,
+) {
+    for (id, value) in prev {
+        match cur.get(id) {
+            None => changes.push(removed(id.clone(), value.clone())),
+            Some(cur_value) if cur_value != value => {
+                changes.push(changed(id.clone(), value.clone(), cur_value.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for (id, value) in cur {
+        if !prev.contains_key(id) {
+            changes.push(added(id.clone(), value.clone()));
+        }
+    }
+}
+
+impl Change {
+    /// JSON representation, e.g.
+    /// `{"change":"rotationKeyAdded","value":"did:key:..."}` or
+    /// `{"change":"serviceChanged","id":"atproto_pds","from":{...},"to":{...}}`.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"change\":");
+        match self {
+            Change::Tombstoned => write_json_string(&mut json, "tombstoned"),
+            Change::RotationKeyAdded(value) => write_value(&mut json, "rotationKeyAdded", value),
+            Change::RotationKeyRemoved(value) => {
+                write_value(&mut json, "rotationKeyRemoved", value)
+            }
+            Change::RotationKeysReordered { from, to } => {
+                write_reordered(&mut json, "rotationKeysReordered", from, to)
+            }
+            Change::VerificationMethodAdded { id, key } => {
+                write_method(&mut json, "verificationMethodAdded", id, key)
+            }
+            Change::VerificationMethodRemoved { id, key } => {
+                write_method(&mut json, "verificationMethodRemoved", id, key)
+            }
+            Change::VerificationMethodChanged { id, from, to } => {
+                write_json_string(&mut json, "verificationMethodChanged");
+                write_field(&mut json, "id", id);
+                write_field(&mut json, "from", from);
+                write_field(&mut json, "to", to);
+            }
+            Change::AlsoKnownAsAdded(value) => write_value(&mut json, "alsoKnownAsAdded", value),
+            Change::AlsoKnownAsRemoved(value) => {
+                write_value(&mut json, "alsoKnownAsRemoved", value)
+            }
+            Change::AlsoKnownAsReordered { from, to } => {
+                write_reordered(&mut json, "alsoKnownAsReordered", from, to)
+            }
+            Change::ServiceAdded { id, service } => {
+                write_service(&mut json, "serviceAdded", id, service)
+            }
+            Change::ServiceRemoved { id, service } => {
+                write_service(&mut json, "serviceRemoved", id, service)
+            }
+            Change::ServiceChanged { id, from, to } => {
+                write_json_string(&mut json, "serviceChanged");
+                write_field(&mut json, "id", id);
+                json.push_str(",\"from\":");
+                write_service_object(&mut json, from);
+                json.push_str(",\"to\":");
+                write_service_object(&mut json, to);
+            }
+        }
+        json.push('}');
+        json
+    }
+}
+
+/// JSON array of the changes returned by `diff_operations`.
+#[cfg(feature = "std")]
+pub fn changes_to_json(changes: &[Change]) -> String {
+    let mut json = String::from("[");
+    for (index, change) in changes.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&change.to_json());
+    }
+    json.push(']');
+    json
+}
+
+#[cfg(feature = "std")]
+fn write_field(json: &mut String, key: &str, value: &str) {
+    json.push(',');
+    write_json_string(json, key);
+    json.push(':');
+    write_json_string(json, value);
+}
+
+#[cfg(feature = "std")]
+fn write_value(json: &mut String, change: &str, value: &str) {
+    write_json_string(json, change);
+    write_field(json, "value", value);
+}
+
+#[cfg(feature = "std")]
+fn write_method(json: &mut String, change: &str, id: &str, key: &str) {
+    write_json_string(json, change);
+    write_field(json, "id", id);
+    write_field(json, "key", key);
+}
+
+#[cfg(feature = "std")]
+fn write_reordered(json: &mut String, change: &str, from: &[String], to: &[String]) {
+    write_json_string(json, change);
+    json.push_str(",\"from\":");
+    write_json_array(json, from);
+    json.push_str(",\"to\":");
+    write_json_array(json, to);
+}
+
+#[cfg(feature = "std")]
+fn write_service(json: &mut String, change: &str, id: &str, service: &Service) {
+    write_json_string(json, change);
+    write_field(json, "id", id);
+    json.push_str(",\"service\":");
+    write_service_object(json, service);
+}
+
+#[cfg(feature = "std")]
+fn write_service_object(json: &mut String, service: &Service) {
+    write_json_object(
+        json,
+        &[("type", &service.r#type), ("endpoint", &service.endpoint)],
+    );
+}
@@ -0,0 +1,174 @@
+// DID document of did:plc, the same as `formatDidDoc` in
+// https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/document.ts
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::json::{write_json_array, write_json_object, write_json_string};
+use crate::operation::{Operation, OperationType};
+use crate::pubkey::{Curve, PublicKey};
+
+const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+const MULTIKEY_CONTEXT: &str = "https://w3id.org/security/multikey/v1";
+const SECP256K1_CONTEXT: &str = "https://w3id.org/security/suites/secp256k1-2019/v1";
+const SECP256R1_CONTEXT: &str = "https://w3id.org/security/suites/ecdsa-2019/v1";
+const MULTIKEY_TYPE: &str = "Multikey";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct DidDocument {
+    #[cfg_attr(feature = "serde", serde(rename = "@context"))]
+    pub context: Vec<String>,
+    pub id: String,
+    pub also_known_as: Vec<String>,
+    pub verification_method: Vec<VerificationMethod>,
+    pub service: Vec<DocumentService>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct VerificationMethod {
+    /// e.g. `did:plc:...#atproto`
+    pub id: String,
+    pub r#type: String,
+    pub controller: String,
+    pub public_key_multibase: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct DocumentService {
+    /// e.g. `#atproto_pds`
+    pub id: String,
+    pub r#type: String,
+    pub service_endpoint: String,
+}
+
+impl DidDocument {
+    /// Formats the DID document from the latest operation of `did`, legacy `create`
+    /// operation included.
+    pub fn from_operation(did: &str, op: &Operation) -> Result<Self, Error> {
+        if op.get_type()? == OperationType::PlcTombstone {
+            return Err(Error::DidTombstoned);
+        }
+        let op = op.normalize()?;
+        let mut context = vec![DID_CONTEXT.to_string(), MULTIKEY_CONTEXT.to_string()];
+
+        let mut verification_method = vec![];
+        for (name, key) in sorted(op.get_verification_methods()?) {
+            let public_key_multibase = key
+                .strip_prefix("did:key:")
+                .ok_or(Error::InvalidVerificationMethods)?
+                .to_string();
+            let key_context = match PublicKey::from_did_key(&key) {
+                Ok(key) => match key.curve() {
+                    Curve::Secp256k1 => SECP256K1_CONTEXT,
+                    Curve::Secp256r1 => SECP256R1_CONTEXT,
+                },
+                Err(_) => MULTIKEY_CONTEXT,
+            };
+            if !context.iter().any(|c| c == key_context) {
+                context.push(key_context.to_string());
+            }
+            verification_method.push(VerificationMethod {
+                id: format!("{}#{}", did, name),
+                r#type: MULTIKEY_TYPE.to_string(),
+                controller: did.to_string(),
+                public_key_multibase,
+            });
+        }
+
+        let service = sorted(op.get_services()?)
+            .into_iter()
+            .map(|(id, service)| DocumentService {
+                id: format!("#{}", id),
+                r#type: service.r#type,
+                service_endpoint: service.endpoint,
+            })
+            .collect();
+
+        Ok(DidDocument {
+            context,
+            id: did.to_string(),
+            also_known_as: op.get_also_known_as()?,
+            verification_method,
+            service,
+        })
+    }
+
+    /// Formats the DID document from a verified operation history, starting with the
+    /// genesis operation. The history itself is not verified again.
+    pub fn from_history<T: AsRef<[u8]>>(history: &[T]) -> Result<Self, Error> {
+        let (genesis, last) = match (history.first(), history.last()) {
+            (Some(genesis), Some(last)) => (genesis, last),
+            _ => return Err(Error::InvalidHistory),
+        };
+        let did = Operation::from_slice(genesis.as_ref())?.get_did()?;
+        Self::from_operation(&did, &Operation::from_slice(last.as_ref())?)
+    }
+
+    /// JSON representation, as served by the PLC directory.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        json.push_str("\"@context\":");
+        write_json_array(&mut json, &self.context);
+        json.push_str(",\"id\":");
+        write_json_string(&mut json, &self.id);
+        json.push_str(",\"alsoKnownAs\":");
+        write_json_array(&mut json, &self.also_known_as);
+        json.push_str(",\"verificationMethod\":[");
+        for (index, method) in self.verification_method.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            write_json_object(
+                &mut json,
+                &[
+                    ("id", &method.id),
+                    ("type", &method.r#type),
+                    ("controller", &method.controller),
+                    ("publicKeyMultibase", &method.public_key_multibase),
+                ],
+            );
+        }
+        json.push_str("],\"service\":[");
+        for (index, service) in self.service.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            write_json_object(
+                &mut json,
+                &[
+                    ("id", &service.id),
+                    ("type", &service.r#type),
+                    ("serviceEndpoint", &service.service_endpoint),
+                ],
+            );
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+// Entries in the order of the DAG-CBOR encoded operation: length first, then bytewise
+fn sorted<V>(map: alloc::collections::BTreeMap<String, V>) -> Vec<(String, V)> {
+    let mut entries: Vec<(String, V)> = map.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| (a.len(), a).cmp(&(b.len(), b)));
+    entries
+}
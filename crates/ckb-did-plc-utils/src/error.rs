@@ -19,6 +19,28 @@ pub enum Error {
     MoleculeError(MoleculeError),
     InvalidCbor,
     InvalidDidFormat,
+    DidTombstoned,
+    NonCanonicalCbor,
+    MissingField,
+    UnexpectedField,
+    InvalidRotationKeysCount,
+    DuplicatedRotationKey,
+    InvalidVerificationMethods,
+    InvalidAlsoKnownAs,
+    InvalidServices,
+    InvalidPrevType,
+    HighSSignature,
+    InvalidCid,
+    MisorderedOperation,
+    LateRecovery,
+    InvalidTimestamp,
+    InvalidJson,
+    OperationTooLarge,
+    HistoryTooLong,
+    TooManyRotationKeys,
+    TooManyServices,
+    TooManyAlsoKnownAs,
+    CheckpointMismatched,
 }
 
 impl From<MoleculeError> for Error {
@@ -34,3 +56,72 @@ impl Display for Error {
 }
 
 impl core::error::Error for Error {}
+
+/// The step of a history verification an error happened at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryStage {
+    /// The history as a whole, e.g. its length or the DID it is verified for
+    History,
+    /// The genesis operation, at index 0
+    Genesis,
+    /// The checkpoint operation of `validate_operation_history_from_checkpoint`, at
+    /// index 0
+    Checkpoint,
+    /// From the operation at `index - 1` to the one at `index`
+    Transition,
+    /// The final authorization, with the rotation keys of the last operation
+    Final,
+}
+
+/// An `Error` of `validate_operation_history`, with its position in the history.
+#[derive(Debug)]
+pub struct HistoryError {
+    /// Index of the operation in the history. 0 for `HistoryStage::History`.
+    pub index: usize,
+    pub stage: HistoryStage,
+    pub error: Error,
+}
+
+impl HistoryError {
+    pub fn new(index: usize, stage: HistoryStage, error: Error) -> Self {
+        HistoryError {
+            index,
+            stage,
+            error,
+        }
+    }
+}
+
+impl From<HistoryError> for Error {
+    fn from(value: HistoryError) -> Self {
+        value.error
+    }
+}
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.stage {
+            HistoryStage::History => write!(f, "history: {:?}", self.error),
+            HistoryStage::Genesis => write!(f, "genesis operation: {:?}", self.error),
+            HistoryStage::Checkpoint => write!(f, "checkpoint operation: {:?}", self.error),
+            HistoryStage::Transition => write!(
+                f,
+                "transition {} -> {}: {:?}",
+                self.index - 1,
+                self.index,
+                self.error
+            ),
+            HistoryStage::Final => write!(
+                f,
+                "final authorization with operation {}: {:?}",
+                self.index, self.error
+            ),
+        }
+    }
+}
+
+impl core::error::Error for HistoryError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
@@ -0,0 +1,234 @@
+// Fork resolution of a did:plc audit log, the same as `assureValidNextOp` in
+// https://github.com/did-method-plc/did-method-plc/blob/main/packages/server/src/constraints.ts
+//
+// An operation may point its `prev` to an earlier operation than the current head,
+// which nullifies every operation after it. This is only allowed when it is signed by
+// a rotation key with higher priority than the one which signed the first nullified
+// operation, and within 72 hours after the first nullified operation was created.
+//
+// Off-chain only: on chain, the history is always a single linear chain.
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use cbor4ii::core::Value;
+
+use crate::cid::Cid;
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::operation::Operation;
+use crate::operation::{OperationType, check_binary_did, genesis_operation, next_operation};
+use crate::stream::StreamedOperation;
+
+/// 72 hours, in milliseconds
+pub const RECOVERY_WINDOW: u64 = 72 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub cid: Cid,
+    /// DAG-CBOR encoded operation
+    pub operation: Vec<u8>,
+    /// Milliseconds since the Unix epoch
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedLog {
+    /// Indices of the canonical chain in the audit log, genesis first
+    pub canonical: Vec<usize>,
+    /// Indices of the nullified operations in the audit log, in order of creation
+    pub nullified: Vec<usize>,
+}
+
+/// Replays an audit log in order of `createdAt` and computes the canonical chain and
+/// the nullified operations, as the PLC directory does when the operations are
+/// submitted. Each link is checked as `validate_2_operations_any_key` does, with every
+/// operation parsed only once.
+pub fn resolve_audit_log(binary_did: &[u8], log: &[LogEntry]) -> Result<ResolvedLog, Error> {
+    let mut resolved = ResolvedLog::default();
+    // parsed operations and the priorities of their signing keys, in log order
+    let mut ops: Vec<StreamedOperation> = Vec::with_capacity(log.len());
+    let mut signers: Vec<usize> = Vec::with_capacity(log.len());
+    for (index, entry) in log.iter().enumerate() {
+        if index > 0 && entry.created_at < log[index - 1].created_at {
+            return Err(Error::MisorderedOperation);
+        }
+        let op = StreamedOperation::from_slice(&entry.operation)?;
+        if op.cid != entry.cid {
+            return Err(Error::InvalidCid);
+        }
+        if resolved.canonical.is_empty() {
+            let op = genesis_operation(op)?;
+            let signer = key_priority(&op, op.find_signing_key(&op.rotation_keys)?);
+            check_binary_did(&op, binary_did)?;
+            ops.push(op);
+            signers.push(signer);
+            resolved.canonical.push(index);
+            continue;
+        }
+
+        let prev = op.prev.ok_or(Error::MisorderedOperation)?;
+        let position = resolved
+            .canonical
+            .iter()
+            .position(|&i| ops[i].cid == prev)
+            .ok_or(Error::MisorderedOperation)?;
+        let prev_op = &ops[resolved.canonical[position]];
+        // the PLC directory rejects any operation after a tombstone as misordered
+        if prev_op.is_tombstone() {
+            return Err(Error::MisorderedOperation);
+        }
+        let op = next_operation(prev_op, || Ok(op))?;
+        let signer = key_priority(prev_op, op.find_signing_key(&prev_op.rotation_keys)?);
+
+        if let Some(&first_nullified) = resolved.canonical.get(position + 1) {
+            // only a rotation key with higher priority can nullify
+            let disputed_signer = signers[first_nullified];
+            if signer >= disputed_signer {
+                #[cfg(feature = "enable_log")]
+                log::warn!(
+                    "rotation key {} can't nullify operation signed by rotation key {}",
+                    signer,
+                    disputed_signer
+                );
+                return Err(Error::VerifySignatureFailed);
+            }
+            if entry.created_at - log[first_nullified].created_at > RECOVERY_WINDOW {
+                return Err(Error::LateRecovery);
+            }
+            let nullified = resolved.canonical.split_off(position + 1);
+            resolved.nullified.extend(nullified);
+        }
+        ops.push(op);
+        signers.push(signer);
+        resolved.canonical.push(index);
+    }
+    if resolved.canonical.is_empty() {
+        return Err(Error::InvalidHistory);
+    }
+    resolved.nullified.sort();
+    Ok(resolved)
+}
+
+// Priority of the rotation key at `index` of `op`, lower is higher. The rotation keys of
+// a legacy operation are `signingKey` and `recoveryKey` in map order, but `recoveryKey`
+// comes first once normalized, see `Operation::normalize`.
+fn key_priority(op: &StreamedOperation, index: usize) -> usize {
+    if op.r#type == OperationType::Create {
+        op.rotation_keys.len() - 1 - index
+    } else {
+        index
+    }
+}
+
+/// Parses the audit log in JSON, as served by `https://plc.directory/:did/log/audit`.
+/// The CID of each entry is checked against its operation.
+#[cfg(feature = "std")]
+pub fn parse_audit_log(json: &str) -> Result<Vec<LogEntry>, Error> {
+    let entries = match crate::json::from_json(json)? {
+        Value::Array(entries) => entries,
+        _ => return Err(Error::InvalidJson),
+    };
+    let mut log = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut fields = match entry {
+            Value::Map(fields) => fields,
+            _ => return Err(Error::InvalidJson),
+        };
+        let mut take = |name: &str| {
+            fields
+                .iter()
+                .position(|(k, _)| matches!(k, Value::Text(k) if k == name))
+                .map(|index| fields.swap_remove(index).1)
+                .ok_or(Error::MissingField)
+        };
+        let (operation, cid, created_at) =
+            match (take("operation")?, take("cid")?, take("createdAt")?) {
+                (Value::Map(raw), Value::Text(cid), Value::Text(created_at)) => {
+                    (Operation::from_raw(raw), cid.parse::<Cid>()?, created_at)
+                }
+                _ => return Err(Error::InvalidJson),
+            };
+        let operation = operation.to_vec()?;
+        if Cid::from_dag_cbor(&operation) != cid {
+            return Err(Error::InvalidCid);
+        }
+        log.push(LogEntry {
+            cid,
+            operation,
+            created_at: parse_created_at(&created_at)?,
+        });
+    }
+    Ok(log)
+}
+
+/// Parses `createdAt` of an audit log entry, e.g. `2023-04-28T17:40:21.519Z`, into
+/// milliseconds since the Unix epoch. Only UTC (`Z`) is accepted.
+pub fn parse_created_at(s: &str) -> Result<u64, Error> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 20 || bytes[bytes.len() - 1] != b'Z' {
+        return Err(Error::InvalidTimestamp);
+    }
+    let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+    if separators.iter().any(|&(i, c)| bytes[i] != c) {
+        return Err(Error::InvalidTimestamp);
+    }
+    let year = digits(&bytes[0..4])?;
+    let month = digits(&bytes[5..7])?;
+    let day = digits(&bytes[8..10])?;
+    let hour = digits(&bytes[11..13])?;
+    let minute = digits(&bytes[14..16])?;
+    let second = digits(&bytes[17..19])?;
+    let millis = match &bytes[19..bytes.len() - 1] {
+        [] => 0,
+        [b'.', fraction @ ..] if (1..=9).contains(&fraction.len()) => {
+            digits(fraction)?;
+            // keep milliseconds only
+            (0..3).fold(0, |acc, i| {
+                acc * 10 + fraction.get(i).map_or(0, |b| (b - b'0') as u64)
+            })
+        }
+        _ => return Err(Error::InvalidTimestamp),
+    };
+    if !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(Error::InvalidTimestamp);
+    }
+    let days = days_from_civil(year, month, day);
+    Ok(((days * 24 + hour) * 60 + minute) * 60 * 1000 + second * 1000 + millis)
+}
+
+fn digits(bytes: &[u8]) -> Result<u64, Error> {
+    bytes.iter().try_fold(0u64, |acc, &b| {
+        if b.is_ascii_digit() {
+            Ok(acc * 10 + (b - b'0') as u64)
+        } else {
+            Err(Error::InvalidTimestamp)
+        }
+    })
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month = (month + 9) % 12;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
@@ -0,0 +1,315 @@
+// Minimal JSON support for the data served by the PLC directory, e.g.
+// `https://plc.directory/did:plc:.../log/audit`.
+//
+// JSON objects are converted into DAG-CBOR maps with sorted keys, so an operation
+// encodes to the same bytes as `@ipld/dag-cbor` does. Only what appears in did:plc
+// data is supported: numbers must be integers.
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use cbor4ii::core::Value;
+
+use crate::dag_cbor::{MAX_DEPTH, dag_cbor_map};
+use crate::error::Error;
+
+pub(crate) fn from_json(json: &str) -> Result<Value, Error> {
+    let mut parser = Parser {
+        buf: json.as_bytes(),
+        pos: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos != parser.buf.len() {
+        return Err(Error::InvalidJson);
+    }
+    Ok(value)
+}
+
+pub(crate) fn to_json(value: &Value) -> Result<String, Error> {
+    let mut json = String::new();
+    write_json_value(&mut json, value)?;
+    Ok(json)
+}
+
+fn write_json_value(json: &mut String, value: &Value) -> Result<(), Error> {
+    match value {
+        Value::Null => json.push_str("null"),
+        Value::Bool(true) => json.push_str("true"),
+        Value::Bool(false) => json.push_str("false"),
+        Value::Integer(i) => {
+            let _ = write!(json, "{}", i);
+        }
+        Value::Text(s) => write_json_string(json, s),
+        Value::Array(items) => {
+            json.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_json_value(json, item)?;
+            }
+            json.push(']');
+        }
+        Value::Map(pairs) => {
+            json.push('{');
+            for (index, (key, value)) in pairs.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                match key {
+                    Value::Text(key) => write_json_string(json, key),
+                    _ => return Err(Error::InvalidJson),
+                }
+                json.push(':');
+                write_json_value(json, value)?;
+            }
+            json.push('}');
+        }
+        // no JSON representation in did:plc data
+        _ => return Err(Error::InvalidJson),
+    }
+    Ok(())
+}
+
+pub(crate) fn write_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{08}' => json.push_str("\\b"),
+            '\u{0c}' => json.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+pub(crate) fn write_json_array(json: &mut String, items: &[String]) {
+    json.push('[');
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        write_json_string(json, item);
+    }
+    json.push(']');
+}
+
+pub(crate) fn write_json_object(json: &mut String, fields: &[(&str, &String)]) {
+    json.push('{');
+    for (index, (key, value)) in fields.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        write_json_string(json, key);
+        json.push(':');
+        write_json_string(json, value);
+    }
+    json.push('}');
+}
+
+struct Parser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.buf.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Result<u8, Error> {
+        self.skip_whitespace();
+        self.buf.get(self.pos).copied().ok_or(Error::InvalidJson)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        if self.peek()? != byte {
+            return Err(Error::InvalidJson);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Value, Error> {
+        if !self.buf[self.pos..].starts_with(literal.as_bytes()) {
+            return Err(Error::InvalidJson);
+        }
+        self.pos += literal.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidJson);
+        }
+        match self.peek()? {
+            b'{' => self.object(depth),
+            b'[' => self.array(depth),
+            b'"' => Ok(Value::Text(self.string()?)),
+            b'n' => self.literal("null", Value::Null),
+            b't' => self.literal("true", Value::Bool(true)),
+            b'f' => self.literal("false", Value::Bool(false)),
+            _ => self.integer(),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, Error> {
+        self.expect(b'{')?;
+        let mut pairs: Vec<(Value, Value)> = vec![];
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Ok(Value::Map(pairs));
+        }
+        loop {
+            let key = self.string()?;
+            if pairs
+                .iter()
+                .any(|(k, _)| matches!(k, Value::Text(k) if *k == key))
+            {
+                return Err(Error::InvalidJson);
+            }
+            self.expect(b':')?;
+            let value = self.value(depth + 1)?;
+            pairs.push((Value::Text(key), value));
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    return Ok(dag_cbor_map(pairs));
+                }
+                _ => return Err(Error::InvalidJson),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, Error> {
+        self.expect(b'[')?;
+        let mut items = vec![];
+        if self.peek()? == b']' {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b']' => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(Error::InvalidJson),
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, Error> {
+        let start = self.pos;
+        if self.buf.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        let digits_start = self.pos;
+        while self.buf.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        let digits = &self.buf[digits_start..self.pos];
+        // no leading zeros, fractions or exponents
+        if digits.is_empty() || (digits.len() > 1 && digits[0] == b'0') {
+            return Err(Error::InvalidJson);
+        }
+        if let Some(b'.' | b'e' | b'E') = self.buf.get(self.pos) {
+            return Err(Error::InvalidJson);
+        }
+        let text =
+            core::str::from_utf8(&self.buf[start..self.pos]).map_err(|_| Error::InvalidJson)?;
+        let integer: i64 = text.parse().map_err(|_| Error::InvalidJson)?;
+        Ok(Value::Integer(integer as i128))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let mut s = String::new();
+        loop {
+            let start = self.pos;
+            while let Some(&b) = self.buf.get(self.pos) {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            // the input is a &str, and the run stops at ASCII bytes only
+            s.push_str(
+                core::str::from_utf8(&self.buf[start..self.pos]).map_err(|_| Error::InvalidJson)?,
+            );
+            match self.buf.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    s.push(self.escape()?);
+                }
+                _ => return Err(Error::InvalidJson),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, Error> {
+        let byte = *self.buf.get(self.pos).ok_or(Error::InvalidJson)?;
+        self.pos += 1;
+        let c = match byte {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{08}',
+            b'f' => '\u{0c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.hex4()?;
+                let code = match high {
+                    0xd800..=0xdbff => {
+                        if !self.buf[self.pos..].starts_with(b"\\u") {
+                            return Err(Error::InvalidJson);
+                        }
+                        self.pos += 2;
+                        let low = self.hex4()?;
+                        if !(0xdc00..=0xdfff).contains(&low) {
+                            return Err(Error::InvalidJson);
+                        }
+                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                    }
+                    _ => high,
+                };
+                char::from_u32(code).ok_or(Error::InvalidJson)?
+            }
+            _ => return Err(Error::InvalidJson),
+        };
+        Ok(c)
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let end = self.pos + 4;
+        let hex = self.buf.get(self.pos..end).ok_or(Error::InvalidJson)?;
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return Err(Error::InvalidJson);
+        }
+        let hex = core::str::from_utf8(hex).map_err(|_| Error::InvalidJson)?;
+        let code = u32::from_str_radix(hex, 16).map_err(|_| Error::InvalidJson)?;
+        self.pos = end;
+        Ok(code)
+    }
+}
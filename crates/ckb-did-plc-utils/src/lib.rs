@@ -1,11 +1,32 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+pub mod authorization;
+#[cfg(feature = "signer")]
+pub mod builder;
+pub mod cid;
+pub mod dag_cbor;
+pub mod did;
+pub mod diff;
+pub mod document;
 pub mod error;
+pub mod fork;
+#[cfg(feature = "std")]
+mod json;
+pub mod limits;
+pub mod local_id;
 pub mod operation;
 pub mod pubkey;
 pub mod reader;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "signer")]
+pub mod signer;
+pub mod stream;
+pub mod verifier;
 // re-exports
 pub use base32;
 pub use base64;
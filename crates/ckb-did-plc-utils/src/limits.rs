@@ -0,0 +1,79 @@
+// Size and count limits of operations and histories.
+//
+// Without them, an operation of any size or a history of any length is accepted until
+// the script runs out of cycles or heap. The limits are checked before the expensive
+// parts (hashing, key decoding, signature verification), so an oversized input fails
+// early with a dedicated error.
+use crate::error::Error;
+use crate::operation::MAX_ROTATION_KEYS;
+
+/// See https://github.com/did-method-plc/did-method-plc/blob/main/packages/server/src/constraints.ts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size of a DAG-CBOR encoded operation, in bytes
+    pub max_operation_size: usize,
+    /// Maximum number of operations in a history
+    pub max_history_length: usize,
+    pub max_rotation_keys: usize,
+    pub max_services: usize,
+    pub max_also_known_as: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_operation_size: 7500,
+            // not limited by PLC, keeps the on-chain verification within the cycle limit
+            max_history_length: 256,
+            max_rotation_keys: MAX_ROTATION_KEYS,
+            max_services: 10,
+            max_also_known_as: 10,
+        }
+    }
+}
+
+impl Limits {
+    /// No limit at all.
+    pub const UNLIMITED: Limits = Limits {
+        max_operation_size: usize::MAX,
+        max_history_length: usize::MAX,
+        max_rotation_keys: usize::MAX,
+        max_services: usize::MAX,
+        max_also_known_as: usize::MAX,
+    };
+
+    pub fn check_history_length(&self, len: usize) -> Result<(), Error> {
+        if len > self.max_history_length {
+            return Err(Error::HistoryTooLong);
+        }
+        Ok(())
+    }
+
+    pub fn check_operation_size(&self, size: usize) -> Result<(), Error> {
+        if size > self.max_operation_size {
+            return Err(Error::OperationTooLarge);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_rotation_keys(&self, count: u64) -> Result<(), Error> {
+        if count > self.max_rotation_keys as u64 {
+            return Err(Error::TooManyRotationKeys);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_services(&self, count: u64) -> Result<(), Error> {
+        if count > self.max_services as u64 {
+            return Err(Error::TooManyServices);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_also_known_as(&self, count: u64) -> Result<(), Error> {
+        if count > self.max_also_known_as as u64 {
+            return Err(Error::TooManyAlsoKnownAs);
+        }
+        Ok(())
+    }
+}
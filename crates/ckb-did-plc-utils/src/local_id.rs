@@ -0,0 +1,146 @@
+// Methods of the local ID of a did:web5 cell.
+//
+// A cell can be linked to a DID of another method, its local ID. Minting or updating it
+// must be authorized by whoever controls that DID, by a proof of its own kind: for
+// did:plc, the history of operations and a signature of a rotation key. Each method
+// implements `LocalIdMethod`, including how its authorization is encoded in the witness,
+// so the on-chain script only dispatches by the prefix of the local ID, see `PREFIX`.
+use alloc::vec::Vec;
+
+use molecule::lazy_reader::Cursor;
+
+use crate::cid::Cid;
+use crate::did::{PLC_DID_PREFIX, PlcDid};
+use crate::error::{Error, HistoryError, HistoryStage};
+use crate::operation::{validate_operation_history, validate_operation_history_from_checkpoint};
+
+pub trait LocalIdMethod {
+    /// The local IDs of the method start with it, e.g. `did:plc:`
+    const PREFIX: &'static str;
+    /// The parsed local ID
+    type Id;
+    /// What proves the control of the local ID, e.g. a did:plc history
+    type Authorization;
+    /// What the verification returns, e.g. the head CID of a did:plc history
+    type Verified;
+    /// The error of both parsing and verifying
+    type Error;
+
+    /// Parses the local ID, prefix included.
+    fn parse_id(id: &str) -> Result<Self::Id, Self::Error>;
+
+    /// Decodes the authorization from `local_id_authorization` of the witness.
+    /// `verified` is what a previous verification returned, if the cell keeps it, e.g.
+    /// the head CID of a did:plc history to continue from. The authorization may still
+    /// prove the control from scratch instead.
+    fn authorization(
+        witness: &Cursor,
+        verified: Option<Self::Verified>,
+    ) -> Result<Self::Authorization, Self::Error>;
+
+    /// Verifies that `authorization` proves the control of `id` and authorizes `msg`,
+    /// see `authorization::local_id_authorization_message`.
+    fn verify(
+        id: &Self::Id,
+        authorization: Self::Authorization,
+        msg: &[u8],
+    ) -> Result<Self::Verified, Self::Error>;
+}
+
+/// did:plc, see `validate_operation_history`.
+pub struct PlcMethod;
+
+/// The authorization of a did:plc local ID.
+pub struct PlcAuthorization {
+    /// From genesis, or from `checkpoint`
+    pub history: Vec<Cursor>,
+    /// One more than the operations from genesis, as many as the operations from
+    /// `checkpoint`, see `validate_operation_history_from_checkpoint`
+    pub rotation_key_indices: Vec<usize>,
+    /// The final authorization signature
+    pub sig: Vec<u8>,
+    /// A trusted head CID, used only when `history` is from it
+    pub checkpoint: Option<Cid>,
+}
+
+impl PlcAuthorization {
+    /// Decodes the molecule table of the witness:
+    ///
+    /// ```text
+    /// table PlcAuthorization {
+    ///     history: BytesVec,
+    ///     sig: Bytes,
+    ///     rotation_key_indices: Uint8Vec,
+    /// }
+    /// ```
+    ///
+    /// The operations are only sliced from `cursor`, not read.
+    pub fn from_cursor(cursor: &Cursor, checkpoint: Option<Cid>) -> Result<Self, Error> {
+        cursor.verify_table(3, false)?;
+        let history = cursor.table_slice_by_index(0)?;
+        history.verify_dynvec()?;
+        let history = (0..history.dynvec_length()?)
+            .map(|index| history.dynvec_slice_by_index(index)?.convert_to_rawbytes())
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes = |index| -> Result<Vec<u8>, Error> {
+            let field = cursor.table_slice_by_index(index)?;
+            field.verify_fixvec(1)?;
+            Ok(field.convert_to_rawbytes()?.try_into()?)
+        };
+        let sig = bytes(1)?;
+        let rotation_key_indices = bytes(2)?.into_iter().map(usize::from).collect();
+        Ok(PlcAuthorization {
+            history,
+            rotation_key_indices,
+            sig,
+            checkpoint,
+        })
+    }
+}
+
+impl LocalIdMethod for PlcMethod {
+    const PREFIX: &'static str = PLC_DID_PREFIX;
+    type Id = PlcDid;
+    type Authorization = PlcAuthorization;
+    /// The CID of the last operation
+    type Verified = Cid;
+    type Error = HistoryError;
+
+    // the DID the history is verified for, an error of the history as a whole
+    fn parse_id(id: &str) -> Result<PlcDid, HistoryError> {
+        id.parse()
+            .map_err(|e| HistoryError::new(0, HistoryStage::History, e))
+    }
+
+    fn authorization(
+        witness: &Cursor,
+        checkpoint: Option<Cid>,
+    ) -> Result<PlcAuthorization, HistoryError> {
+        PlcAuthorization::from_cursor(witness, checkpoint)
+            .map_err(|e| HistoryError::new(0, HistoryStage::History, e))
+    }
+
+    // The checkpoint only shortens a history starting at it. A history from genesis is
+    // verified as a whole instead and may end on another fork: after a recovery, the
+    // checkpoint can be on a cancelled one.
+    fn verify(id: &PlcDid, auth: PlcAuthorization, msg: &[u8]) -> Result<Cid, HistoryError> {
+        match auth.checkpoint {
+            Some(checkpoint) if auth.rotation_key_indices.len() == auth.history.len() => {
+                validate_operation_history_from_checkpoint(
+                    &checkpoint,
+                    auth.history,
+                    auth.rotation_key_indices,
+                    msg,
+                    &auth.sig,
+                )
+            }
+            _ => validate_operation_history(
+                id.as_bytes(),
+                auth.history,
+                auth.rotation_key_indices,
+                msg,
+                &auth.sig,
+            ),
+        }
+    }
+}
@@ -1,13 +1,13 @@
 // Implementation choices:
-// 1. No serde used. It would make code bloat and is not necessary.
+// 1. No serde used on chain. It would make code bloat and is not necessary. The
+//    optional `serde` feature is for off-chain use only, see `serde_impl.rs`.
 // 2. No CID crate used. The CID implementation would require multibase, multihash,
-//    and multicodec support, which adds too many dependency crates.
-use alloc::borrow::ToOwned;
+//    and multicodec support, which adds too many dependency crates. See `cid.rs`.
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::{format, vec};
 
-use base32::Alphabet;
 use cbor4ii::core::dec::Decode;
 use cbor4ii::core::enc::Encode;
 use cbor4ii::core::utils::{BufWriter, SliceReader};
@@ -17,22 +17,90 @@ use base64::Engine;
 use molecule::lazy_reader::Cursor;
 use sha2::{Digest, Sha256};
 
-use crate::error::Error;
+use crate::cid::Cid;
+use crate::dag_cbor::{dag_cbor_map, sort_map_keys, validate_dag_cbor};
+use crate::did::PlcDid;
+use crate::error::{Error, HistoryError, HistoryStage};
+use crate::limits::Limits;
 use crate::pubkey::PublicKey;
+use crate::stream::StreamedOperation;
 
-// this is the only one valid local id so far
-const LOCAL_ID_PREFIX: &str = "did:plc:";
+// https://github.com/did-method-plc/did-method-plc/blob/main/packages/server/src/constraints.ts
+pub(crate) const MAX_ROTATION_KEYS: usize = 5;
 
+// Parses a did:plc local ID into the binary form of the DID. See `local_id` for the
+// dispatch by DID method.
 pub fn parse_local_id(id: &[u8]) -> Result<Vec<u8>, Error> {
     let str = core::str::from_utf8(id).map_err(|_| Error::InvalidDidFormat)?;
-    if let Some(str) = str.strip_prefix(LOCAL_ID_PREFIX) {
-        base32::decode(Alphabet::Rfc4648Lower { padding: false }, str)
-            .ok_or(Error::InvalidDidFormat)
-    } else {
-        Err(Error::InvalidDidFormat)
+    let did: PlcDid = str.parse()?;
+    Ok(did.as_bytes().to_vec())
+}
+
+/// The kind of a DID PLC operation, taken from its `type` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationType {
+    /// Legacy genesis operation, `type` is "create"
+    Create,
+    /// `type` is "plc_operation"
+    PlcOperation,
+    /// `type` is "plc_tombstone"
+    PlcTombstone,
+}
+
+impl OperationType {
+    // Fields of the operation, as defined in
+    // https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/types.ts
+    pub(crate) fn fields(&self) -> &'static [&'static str] {
+        match self {
+            OperationType::Create => &[
+                "type",
+                "signingKey",
+                "recoveryKey",
+                "handle",
+                "service",
+                "prev",
+                "sig",
+            ],
+            OperationType::PlcOperation => &[
+                "type",
+                "rotationKeys",
+                "verificationMethods",
+                "alsoKnownAs",
+                "services",
+                "prev",
+                "sig",
+            ],
+            OperationType::PlcTombstone => &["type", "prev", "sig"],
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Result<Self, Error> {
+        match value {
+            "create" => Ok(OperationType::Create),
+            "plc_operation" => Ok(OperationType::PlcOperation),
+            "plc_tombstone" => Ok(OperationType::PlcTombstone),
+            _ => Err(Error::InvalidOperation),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationType::Create => "create",
+            OperationType::PlcOperation => "plc_operation",
+            OperationType::PlcTombstone => "plc_tombstone",
+        }
     }
 }
 
+/// An entry of the `services` field, e.g. `atproto_pds`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Service {
+    pub r#type: String,
+    pub endpoint: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Operation {
     raw: Vec<(Value, Value)>,
 }
@@ -50,56 +118,147 @@ impl Operation {
         Ok(Operation { raw })
     }
 
-    pub(crate) fn new_unsigned_operation(&self) -> Result<Self, Error> {
-        let mut unsigned_raw = vec![];
-        for (key, value) in &self.raw {
-            if let Value::Text(key_str) = key {
-                // Creates an unsigned operation by removing the "sig" field while preserving
-                // the original key/value pairs and their order. This unsigned operation will
-                // be used for signature verification.
-                if key_str != "sig" {
-                    unsigned_raw.push((key.clone(), value.clone()));
-                }
+    /// Same as `from_slice`, but rejects anything that is not canonical DAG-CBOR,
+    /// so the CID generated from the operation is the CID of `buf` itself.
+    pub fn from_slice_strict(buf: &[u8]) -> Result<Self, Error> {
+        validate_dag_cbor(buf)?;
+        Self::from_slice(buf)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn from_raw(raw: Vec<(Value, Value)>) -> Self {
+        Operation { raw }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn into_raw(self) -> Vec<(Value, Value)> {
+        self.raw
+    }
+
+    /// Encodes the operation as DAG-CBOR, keeping the key/value pairs in their order.
+    pub fn to_vec(&self) -> Result<Vec<u8>, Error> {
+        let mut writer = BufWriter::new(Vec::new());
+        types::Map(self.raw.as_slice())
+            .encode(&mut writer)
+            .map_err(|_| Error::InvalidOperation)?;
+        Ok(writer.into_inner())
+    }
+
+    /// Parses an operation in JSON, as served by the PLC directory. The keys are
+    /// sorted, so `to_vec` gives the same bytes as `@ipld/dag-cbor`.
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        match crate::json::from_json(json)? {
+            Value::Map(raw) => Ok(Operation { raw }),
+            _ => Err(Error::InvalidOperation),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        crate::json::to_json(&Value::Map(self.raw.clone()))
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_value(value: Value) -> Result<Self, Error> {
+        match value {
+            Value::Map(raw) => Ok(Operation { raw }),
+            _ => Err(Error::InvalidOperation),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn raw(&self) -> &[(Value, Value)] {
+        &self.raw
+    }
+
+    /// Validates the operation against the did:plc schema:
+    /// https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/types.ts
+    pub fn validate(&self) -> Result<(), Error> {
+        let op_type = self.get_type()?;
+        let fields = op_type.fields();
+        if !self.has_keys(fields) {
+            return Err(Error::MissingField);
+        }
+        for (k, _) in &self.raw {
+            match k {
+                Value::Text(k) if fields.contains(&k.as_str()) => {}
+                _ => return Err(Error::UnexpectedField),
             }
         }
-        Ok(Operation { raw: unsigned_raw })
+        if !matches!(self.get_field("sig"), Some(Value::Text(_))) {
+            return Err(Error::InvalidSignature);
+        }
+        match (op_type, self.get_field("prev")) {
+            // legacy operation only exists as genesis operation
+            (OperationType::Create, Some(Value::Null)) => {}
+            (OperationType::PlcOperation, Some(Value::Null | Value::Text(_))) => {}
+            (OperationType::PlcTombstone, Some(Value::Text(_))) => {}
+            _ => return Err(Error::InvalidPrevType),
+        }
+        self.get_prev()?;
+        match op_type {
+            OperationType::Create => self.validate_legacy_fields(),
+            OperationType::PlcOperation => self.validate_operation_fields(),
+            OperationType::PlcTombstone => Ok(()),
+        }
     }
 
-    pub(crate) fn validate(&self) -> Result<(), Error> {
-        if self.is_legacy() {
-            if self.has_keys(&[
-                "type",
-                "signingKey",
-                "recoveryKey",
-                "handle",
-                "service",
-                "prev",
-                "sig",
-            ]) {
-                Ok(())
-            } else {
-                Err(Error::InvalidOperation)
+    fn validate_legacy_fields(&self) -> Result<(), Error> {
+        self.get_legacy_rotation_keys()?;
+        for key in ["signingKey", "recoveryKey"] {
+            if !matches!(self.get_field(key), Some(Value::Text(_))) {
+                return Err(Error::InvalidKey);
             }
-        } else if self.is_operation() {
-            if self.has_keys(&[
-                "type",
-                "rotationKeys",
-                "verificationMethods",
-                "alsoKnownAs",
-                "services",
-                "prev",
-                "sig",
-            ]) {
-                Ok(())
-            } else {
-                Err(Error::InvalidOperation)
+        }
+        if !matches!(self.get_field("handle"), Some(Value::Text(_))) {
+            return Err(Error::InvalidAlsoKnownAs);
+        }
+        if !matches!(self.get_field("service"), Some(Value::Text(_))) {
+            return Err(Error::InvalidServices);
+        }
+        Ok(())
+    }
+
+    fn validate_operation_fields(&self) -> Result<(), Error> {
+        let Some(Value::Array(rotation_keys)) = self.get_field("rotationKeys") else {
+            return Err(Error::RotationKeysDecodeError);
+        };
+        if rotation_keys.is_empty() || rotation_keys.len() > MAX_ROTATION_KEYS {
+            return Err(Error::InvalidRotationKeysCount);
+        }
+        for (index, key) in rotation_keys.iter().enumerate() {
+            let Value::Text(key) = key else {
+                return Err(Error::RotationKeysDecodeError);
+            };
+            PublicKey::from_did_key(key)?;
+            if rotation_keys[..index].contains(&Value::Text(key.clone())) {
+                return Err(Error::DuplicatedRotationKey);
             }
-        } else {
-            Err(Error::InvalidOperation)
+        }
+        let verification_methods = self
+            .get_verification_methods()
+            .map_err(|_| Error::InvalidVerificationMethods)?;
+        if verification_methods
+            .values()
+            .any(|key| !key.starts_with("did:key:"))
+        {
+            return Err(Error::InvalidVerificationMethods);
+        }
+        self.get_also_known_as()
+            .map_err(|_| Error::InvalidAlsoKnownAs)?;
+        self.get_services().map_err(|_| Error::InvalidServices)?;
+        Ok(())
+    }
+
+    pub fn get_type(&self) -> Result<OperationType, Error> {
+        match self.get_field("type") {
+            Some(Value::Text(value)) => OperationType::parse(value),
+            _ => Err(Error::InvalidOperation),
         }
     }
 
-    pub(crate) fn is_operation(&self) -> bool {
+    pub fn is_operation(&self) -> bool {
         for (k, v) in &self.raw {
             if let (Value::Text(key), Value::Text(value)) = (k, v) {
                 if key == "type" && value == "plc_operation" {
@@ -110,7 +269,18 @@ impl Operation {
         false
     }
 
-    pub(crate) fn is_legacy(&self) -> bool {
+    pub fn is_tombstone(&self) -> bool {
+        for (k, v) in &self.raw {
+            if let (Value::Text(key), Value::Text(value)) = (k, v) {
+                if key == "type" && value == "plc_tombstone" {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn is_legacy(&self) -> bool {
         for (k, v) in &self.raw {
             if let (Value::Text(key), Value::Text(value)) = (k, v) {
                 if key == "type" && value == "create" {
@@ -121,16 +291,16 @@ impl Operation {
         false
     }
 
-    pub(crate) fn get_rotation_keys(&self) -> Result<Vec<PublicKey>, Error> {
+    pub fn get_rotation_keys(&self) -> Result<Vec<PublicKey>, Error> {
         for (k, v) in &self.raw {
             if let (Value::Text(key), Value::Array(value)) = (k, v) {
                 if key == "rotationKeys" {
                     let mut rotation_keys = vec![];
                     for item in value {
-                        if let Value::Text(key) = item {
-                            let key = PublicKey::from_str(key)?;
-                            rotation_keys.push(key);
-                        }
+                        let Value::Text(key) = item else {
+                            return Err(Error::RotationKeysDecodeError);
+                        };
+                        rotation_keys.push(PublicKey::from_did_key(key)?);
                     }
                     return Ok(rotation_keys);
                 }
@@ -140,109 +310,155 @@ impl Operation {
     }
 
     // "signingKey" and "recoveryKey" are both used as rotation keys for legacy operation
-    pub(crate) fn get_legacy_rotation_keys(&self) -> Result<Vec<PublicKey>, Error> {
+    pub fn get_legacy_rotation_keys(&self) -> Result<Vec<PublicKey>, Error> {
         let mut pubkeys = vec![];
         for (k, v) in &self.raw {
             if let (Value::Text(key), Value::Text(value)) = (k, v) {
                 if key == "signingKey" || key == "recoveryKey" {
-                    pubkeys.push(PublicKey::from_str(value)?);
+                    pubkeys.push(PublicKey::from_did_key(value)?);
                 }
             }
         }
         Ok(pubkeys)
     }
 
-    pub(crate) fn get_signature(&self) -> Result<Vec<u8>, Error> {
-        for (k, v) in &self.raw {
-            if let (Value::Text(key), Value::Text(value)) = (k, v) {
-                if key == "sig" {
-                    // https://github.com/did-method-plc/did-method-plc/blob/bd5825589a34d1abb377943389ac3838a15cd110/packages/lib/src/operations.ts#L268
-                    if value.ends_with("=") {
-                        return Err(Error::InvalidSignaturePadding);
+    /// Converts a legacy `create` operation into the equivalent `plc_operation`, the same
+    /// as `normalizeOp` in
+    /// https://github.com/did-method-plc/did-method-plc/blob/main/packages/lib/src/operations.ts
+    /// Other operations are returned as is. The normalized operation is only a view of
+    /// the document data: its CID differs from the original one and its signature
+    /// doesn't verify.
+    pub fn normalize(&self) -> Result<Operation, Error> {
+        if !self.is_legacy() {
+            return Ok(self.clone());
+        }
+        let text_field = |key: &str| match self.get_field(key) {
+            Some(Value::Text(value)) => Ok(value.clone()),
+            _ => Err(Error::InvalidOperation),
+        };
+        let signing_key = text_field("signingKey")?;
+        let recovery_key = text_field("recoveryKey")?;
+        let handle = text_field("handle")?;
+        let service = text_field("service")?;
+        let pds = dag_cbor_map(vec![
+            (text("type"), text("AtprotoPersonalDataServer")),
+            (text("endpoint"), text(&ensure_http_prefix(&service))),
+        ]);
+
+        let mut raw = vec![
+            (text("type"), text("plc_operation")),
+            (
+                text("verificationMethods"),
+                dag_cbor_map(vec![(text("atproto"), text(&signing_key))]),
+            ),
+            (
+                text("rotationKeys"),
+                Value::Array(vec![text(&recovery_key), text(&signing_key)]),
+            ),
+            (
+                text("alsoKnownAs"),
+                Value::Array(vec![text(&ensure_atproto_prefix(&handle))]),
+            ),
+            (
+                text("services"),
+                dag_cbor_map(vec![(text("atproto_pds"), pds)]),
+            ),
+        ];
+        for key in ["prev", "sig"] {
+            if let Some(value) = self.get_field(key) {
+                raw.push((text(key), value.clone()));
+            }
+        }
+        sort_map_keys(&mut raw);
+        Ok(Operation { raw })
+    }
+
+    // verification method name (e.g. "atproto") -> did:key
+    pub fn get_verification_methods(&self) -> Result<BTreeMap<String, String>, Error> {
+        let mut methods = BTreeMap::new();
+        match self.get_field("verificationMethods") {
+            Some(Value::Map(map)) => {
+                for (k, v) in map {
+                    if let (Value::Text(name), Value::Text(key)) = (k, v) {
+                        methods.insert(name.clone(), key.clone());
+                    } else {
+                        return Err(Error::InvalidOperation);
                     }
-                    let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
-                    let decoded_sig = engine.decode(value).map_err(|_| Error::InvalidSignature)?;
-                    return Ok(decoded_sig);
                 }
+                Ok(methods)
             }
+            _ => Err(Error::InvalidOperation),
         }
-        Err(Error::InvalidOperation)
     }
 
-    // note, the `pubkeys`` are from previous operation
-    pub(crate) fn verify_signature(
-        &self,
-        pubkeys: &[PublicKey],
-        rotation_key_index: usize,
-    ) -> Result<(), Error> {
-        let unsigned_op = self.new_unsigned_operation()?;
-        let sig = self.get_signature()?;
-        let mut writer = BufWriter::new(Vec::new());
-
-        // Convert Vec<(Value, Value)> back to Value::Map for encoding
-        let map_value = Value::Map(unsigned_op.raw);
-        map_value
-            .encode(&mut writer)
-            .map_err(|_| Error::InvalidOperation)?;
-        let msg = writer.into_inner();
-
-        if rotation_key_index >= pubkeys.len() {
-            return Err(Error::InvalidKeyIndex);
+    pub fn get_also_known_as(&self) -> Result<Vec<String>, Error> {
+        match self.get_field("alsoKnownAs") {
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item {
+                    Value::Text(item) => Ok(item.clone()),
+                    _ => Err(Error::InvalidOperation),
+                })
+                .collect(),
+            _ => Err(Error::InvalidOperation),
         }
-        if pubkeys[rotation_key_index].verify(&msg, &sig).is_ok() {
-            Ok(())
-        } else {
-            #[cfg(feature = "enable_log")]
-            {
-                log::warn!("verify signature failed");
-                log::warn!("sig: (length = {}), {}", sig.len(), hex::encode(sig));
-                log::warn!("msg: (length = {}), {}", msg.len(), hex::encode(msg));
-                log::warn!("rotation_key_index = {}", rotation_key_index);
-                for pubkey in pubkeys {
-                    let pubkey = pubkey.raw();
-                    log::warn!(
-                        "pubkey: (length = {}), {}",
-                        pubkey.len(),
-                        hex::encode(pubkey)
-                    );
+    }
+
+    // service id (e.g. "atproto_pds") -> service
+    pub fn get_services(&self) -> Result<BTreeMap<String, Service>, Error> {
+        let mut services = BTreeMap::new();
+        match self.get_field("services") {
+            Some(Value::Map(map)) => {
+                for (k, v) in map {
+                    let (Value::Text(id), Value::Map(service)) = (k, v) else {
+                        return Err(Error::InvalidOperation);
+                    };
+                    let mut r#type = None;
+                    let mut endpoint = None;
+                    for (k, v) in service {
+                        match (k, v) {
+                            (Value::Text(k), Value::Text(v)) if k == "type" => {
+                                r#type = Some(v.clone())
+                            }
+                            (Value::Text(k), Value::Text(v)) if k == "endpoint" => {
+                                endpoint = Some(v.clone())
+                            }
+                            _ => return Err(Error::InvalidOperation),
+                        }
+                    }
+                    let (Some(r#type), Some(endpoint)) = (r#type, endpoint) else {
+                        return Err(Error::InvalidOperation);
+                    };
+                    services.insert(id.clone(), Service { r#type, endpoint });
                 }
+                Ok(services)
             }
-            Err(Error::VerifySignatureFailed)
+            _ => Err(Error::InvalidOperation),
         }
     }
 
-    pub fn generate_cid(&self) -> Result<String, Error> {
-        let mut writer = BufWriter::new(Vec::new());
-
-        let map_value = types::Map(self.raw.as_slice());
-        map_value
-            .encode(&mut writer)
-            .map_err(|_| Error::InvalidOperation)?;
-        let dag = writer.into_inner();
-        let hashed = Sha256::digest(dag.as_slice());
-
-        // the following algorithm is to assemble CID manually:
-        // CIDv1
-        // base32 multibase encoding (prefix: b)
-        // dag-cbor multibase type (code: 0x71)
-        // sha-256 multihash (code: 0x12)
-        let mut raw_cid = vec![0x01, 0x71, 0x12, 0x20];
-        raw_cid.extend_from_slice(hashed.as_slice());
+    pub fn get_signature(&self) -> Result<Vec<u8>, Error> {
+        for (k, v) in &self.raw {
+            if let (Value::Text(key), Value::Text(value)) = (k, v) {
+                if key == "sig" {
+                    return decode_signature(value);
+                }
+            }
+        }
+        Err(Error::InvalidOperation)
+    }
 
-        let b32 = base32::encode(
-            Alphabet::Rfc4648Lower { padding: false },
-            raw_cid.as_slice(),
-        );
-        Ok("b".to_owned() + &b32)
+    pub fn generate_cid(&self) -> Result<Cid, Error> {
+        Ok(Cid::from_dag_cbor(&self.to_vec()?))
     }
 
     // The `prev` field can be null for genesis operation
-    pub(crate) fn get_prev(&self) -> Result<Option<String>, Error> {
+    pub fn get_prev(&self) -> Result<Option<Cid>, Error> {
         for (k, v) in &self.raw {
             if let Value::Text(key) = k {
                 if key == "prev" {
                     if let Value::Text(value) = v {
-                        return Ok(Some(value.clone()));
+                        return Ok(Some(value.parse()?));
                     } else if let Value::Null = v {
                         return Ok(None);
                     } else {
@@ -254,17 +470,15 @@ impl Operation {
         Err(Error::InvalidOperation)
     }
 
-    #[allow(unused)]
-    pub(crate) fn get_did(&self) -> Result<String, Error> {
-        let binary_did = self.get_binary_did()?;
-        let b32 = base32::encode(
-            Alphabet::Rfc4648Lower { padding: false },
-            binary_did.as_slice(),
-        );
-        Ok(format!("did:plc:{}", b32))
+    pub fn get_did(&self) -> Result<String, Error> {
+        Ok(format!("{}", self.get_plc_did()?))
+    }
+
+    pub fn get_plc_did(&self) -> Result<PlcDid, Error> {
+        PlcDid::from_bytes(&self.get_binary_did()?)
     }
 
-    pub(crate) fn get_binary_did(&self) -> Result<Vec<u8>, Error> {
+    pub fn get_binary_did(&self) -> Result<Vec<u8>, Error> {
         let mut writer = BufWriter::new(Vec::new());
 
         let map_value = types::Map(self.raw.as_slice());
@@ -278,6 +492,13 @@ impl Operation {
         Ok(hashed[0..15].to_vec())
     }
 
+    fn get_field(&self, key: &str) -> Option<&Value> {
+        self.raw.iter().find_map(|(k, v)| match k {
+            Value::Text(k) if k == key => Some(v),
+            _ => None,
+        })
+    }
+
     fn has_keys(&self, keys: &[&str]) -> bool {
         keys.iter().all(|&key| {
             self.raw.iter().any(|(k, _)| {
@@ -291,47 +512,84 @@ impl Operation {
     }
 }
 
+// https://github.com/did-method-plc/did-method-plc/blob/bd5825589a34d1abb377943389ac3838a15cd110/packages/lib/src/operations.ts#L268
+pub(crate) fn decode_signature(sig: &str) -> Result<Vec<u8>, Error> {
+    if sig.ends_with("=") {
+        return Err(Error::InvalidSignaturePadding);
+    }
+    let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    engine.decode(sig).map_err(|_| Error::InvalidSignature)
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.into())
+}
+
+fn ensure_atproto_prefix(handle: &str) -> String {
+    if handle.starts_with("at://") {
+        return handle.into();
+    }
+    let stripped = handle
+        .replacen("http://", "", 1)
+        .replacen("https://", "", 1);
+    format!("at://{}", stripped)
+}
+
+fn ensure_http_prefix(service: &str) -> String {
+    if service.starts_with("http://") || service.starts_with("https://") {
+        return service.into();
+    }
+    format!("https://{}", service)
+}
+
 // steps to verify 2 DID PLC operations:
-// * deserialize previous operation
-// * validate previous operation
-// * deserialize current operation
-// * validate current operation
-// * generate cid from previous operation
-// * verify cid is same in current operation
-// * get all rotation keys from previous operation
-// * create unsigned operation from current operation
-// * verify signature in current operation with current unsigned operation and rotation keys
+// * parse and validate previous operation, which must be canonical DAG-CBOR. Its CID
+//   is computed from the same bytes while parsing
+// * a tombstone can't be followed by any operation
+// * parse and validate current operation. The digest of its unsigned form is computed
+//   from the same bytes while parsing
+// * verify cid of previous operation is same in current operation
+// * verify signature in current operation with rotation keys of previous operation
 pub fn validate_2_operations(
     prev_buf: &[u8],
     cur_buf: &[u8],
     rotation_key_index: usize,
 ) -> Result<(), Error> {
-    let prev_op = Operation::from_slice(prev_buf)?;
-    let cur_op = Operation::from_slice(cur_buf)?;
-    prev_op.validate()?;
-    cur_op.validate()?;
-    let cid = prev_op.generate_cid()?;
-    match cur_op.get_prev()? {
-        Some(prev) => {
-            if prev != cid {
-                #[cfg(feature = "enable_log")]
-                {
-                    log::warn!("invalid prev");
-                    log::warn!("cid: {}", cid);
-                    log::warn!("prev: {}", prev);
-                }
-                return Err(Error::InvalidPrev);
+    let prev = StreamedOperation::from_slice(prev_buf)?;
+    let cur = next_operation(&prev, || StreamedOperation::from_slice(cur_buf))?;
+    cur.verify_signature(&prev.rotation_keys, rotation_key_index)
+}
+
+/// Same as `validate_2_operations`, but tries every rotation key of the previous
+/// operation and returns the index of the one that signed the current operation.
+pub fn validate_2_operations_any_key(prev_buf: &[u8], cur_buf: &[u8]) -> Result<usize, Error> {
+    let prev = StreamedOperation::from_slice(prev_buf)?;
+    let cur = next_operation(&prev, || StreamedOperation::from_slice(cur_buf))?;
+    cur.find_signing_key(&prev.rotation_keys)
+}
+
+// Parses the operation following `prev` and checks its `prev` field
+pub(crate) fn next_operation(
+    prev: &StreamedOperation,
+    parse: impl FnOnce() -> Result<StreamedOperation, Error>,
+) -> Result<StreamedOperation, Error> {
+    if prev.is_tombstone() {
+        return Err(Error::DidTombstoned);
+    }
+    let cur = parse()?;
+    match cur.prev {
+        Some(cid) if cid == prev.cid => Ok(cur),
+        Some(_cid) => {
+            #[cfg(feature = "enable_log")]
+            {
+                log::warn!("invalid prev");
+                log::warn!("cid: {}", prev.cid);
+                log::warn!("prev: {}", _cid);
             }
+            Err(Error::InvalidPrev)
         }
-        None => return Err(Error::MissingPrevField),
+        None => Err(Error::MissingPrevField),
     }
-    let rotation_keys = if prev_op.is_legacy() {
-        prev_op.get_legacy_rotation_keys()?
-    } else {
-        prev_op.get_rotation_keys()?
-    };
-    cur_op.verify_signature(&rotation_keys, rotation_key_index)?;
-    Ok(())
 }
 
 pub fn validate_genesis_operation(
@@ -339,41 +597,105 @@ pub fn validate_genesis_operation(
     binary_did: &[u8],
     rotation_key_index: usize,
 ) -> Result<(), Error> {
-    let op = Operation::from_slice(buf)?;
-    op.validate()?;
-    let prev = op.get_prev()?;
-    if prev.is_some() {
+    let op = genesis_operation(StreamedOperation::from_slice(buf)?)?;
+    op.verify_signature(&op.rotation_keys, rotation_key_index)?;
+    check_binary_did(&op, binary_did)
+}
+
+/// Same as `validate_genesis_operation`, but tries every rotation key and returns
+/// the index of the one that signed the genesis operation.
+pub fn validate_genesis_operation_any_key(buf: &[u8], binary_did: &[u8]) -> Result<usize, Error> {
+    let op = genesis_operation(StreamedOperation::from_slice(buf)?)?;
+    let rotation_key_index = op.find_signing_key(&op.rotation_keys)?;
+    check_binary_did(&op, binary_did)?;
+    Ok(rotation_key_index)
+}
+
+pub(crate) fn genesis_operation(op: StreamedOperation) -> Result<StreamedOperation, Error> {
+    if op.prev.is_some() || op.is_tombstone() {
         return Err(Error::NotGenesisOperation);
     }
-    let rotation_keys = if op.is_legacy() {
-        op.get_legacy_rotation_keys()?
-    } else {
-        op.get_rotation_keys()?
-    };
-    op.verify_signature(&rotation_keys, rotation_key_index)?;
-    let expected_did = op.get_binary_did()?;
-    if binary_did != expected_did {
+    Ok(op)
+}
+
+pub(crate) fn check_binary_did(op: &StreamedOperation, binary_did: &[u8]) -> Result<(), Error> {
+    if binary_did != op.binary_did() {
         #[cfg(feature = "enable_log")]
         {
             log::warn!("did mismatched");
             log::warn!("did: {:?}", binary_did);
-            log::warn!("expected did: {:?}", expected_did);
+            log::warn!("expected did: {:?}", op.binary_did());
         }
         return Err(Error::DidMismatched);
     }
     Ok(())
 }
 
-fn validate_final_operation(
-    buf: &[u8],
+// Verifies the history from the genesis operation, each operation is parsed once by
+// `parse`. `verify` checks the signature of an operation with the rotation keys of its
+// previous operation (its own for genesis). Returns the last operation, which is not
+// a tombstone.
+fn verify_history(
+    binary_did: &[u8],
+    history_len: usize,
+    parse: impl Fn(usize) -> Result<StreamedOperation, Error>,
+    mut verify: impl FnMut(usize, &StreamedOperation, &[PublicKey]) -> Result<(), Error>,
+) -> Result<StreamedOperation, HistoryError> {
+    if history_len == 0 {
+        return Err(HistoryError::new(
+            0,
+            HistoryStage::History,
+            Error::InvalidHistory,
+        ));
+    }
+    let genesis = (|| {
+        let genesis = genesis_operation(parse(0)?)?;
+        verify(0, &genesis, &genesis.rotation_keys)?;
+        check_binary_did(&genesis, binary_did)?;
+        Ok(genesis)
+    })()
+    .map_err(|e| HistoryError::new(0, HistoryStage::Genesis, e))?;
+    verify_transitions(genesis, history_len, parse, verify)
+}
+
+// Verifies the operations from index 1 on, `prev` being the operation at index 0. Returns
+// the last operation, which is not a tombstone.
+fn verify_transitions(
+    mut prev: StreamedOperation,
+    history_len: usize,
+    parse: impl Fn(usize) -> Result<StreamedOperation, Error>,
+    mut verify: impl FnMut(usize, &StreamedOperation, &[PublicKey]) -> Result<(), Error>,
+) -> Result<StreamedOperation, HistoryError> {
+    for index in 1..history_len {
+        let cur = next_operation(&prev, || parse(index))
+            .and_then(|cur| verify(index, &cur, &prev.rotation_keys).map(|_| cur))
+            .map_err(|e| HistoryError::new(index, HistoryStage::Transition, e))?;
+        prev = cur;
+    }
+    // The DID is deactivated by a tombstone, there is no rotation key left to authorize with
+    if prev.is_tombstone() {
+        return Err(HistoryError::new(
+            history_len - 1,
+            HistoryStage::Final,
+            Error::DidTombstoned,
+        ));
+    }
+    Ok(prev)
+}
+
+fn find_final_rotation_key_index(
+    op: &StreamedOperation,
     final_sig: &[u8],
     msg: &[u8],
-    rotation_key_index: usize,
-) -> Result<(), Error> {
-    let op = Operation::from_slice(buf)?;
-    let rotation_keys = op.get_rotation_keys()?;
-    rotation_keys[rotation_key_index].verify(msg, final_sig)?;
-    Ok(())
+) -> Result<usize, Error> {
+    for (index, key) in op.rotation_keys.iter().enumerate() {
+        match key.verify(msg, final_sig) {
+            Ok(()) => return Ok(index),
+            Err(Error::HighSSignature) => return Err(Error::HighSSignature),
+            Err(_) => continue,
+        }
+    }
+    Err(Error::VerifySignatureFailed)
 }
 
 /// Validates a complete DID PLC operation history chain and final authorization signature.
@@ -393,36 +715,222 @@ fn validate_final_operation(
 ///   - `rotation_key_indices[0]`: Index for genesis operation signature
 ///   - `rotation_key_indices[1..history_len]`: Indices for transitions between operations
 ///   - `rotation_key_indices[history_len]`: Index for final authorization signature
-/// * `msg` - The message that was signed for the final authorization
+/// * `msg` - The message that was signed for the final authorization, built by
+///   `authorization::authorization_message` on chain
 /// * `final_sig` - The signature authorizing the DID PLC operation on-chain
 ///
+/// A `plc_tombstone` operation is terminal: a history containing one can't authorize
+/// anything on-chain and results in `Error::DidTombstoned`.
+///
+/// The default `Limits` apply, see `validate_operation_history_with_limits`.
+///
+/// Returns the CID of the last operation, which can be used as the checkpoint of
+/// `validate_operation_history_from_checkpoint` later. On failure, the returned
+/// `HistoryError` tells which operation and which step failed.
 pub fn validate_operation_history(
     binary_did: &[u8],
     history: Vec<Cursor>,
     rotation_key_indices: Vec<usize>,
     msg: &[u8],
     final_sig: &[u8],
-) -> Result<(), Error> {
+) -> Result<Cid, HistoryError> {
+    validate_operation_history_with_limits(
+        binary_did,
+        history,
+        rotation_key_indices,
+        msg,
+        final_sig,
+        &Limits::default(),
+    )
+}
+
+/// Like `validate_operation_history`, with the given `limits`. The history length and
+/// the size of every operation are checked before any operation is parsed; the number of
+/// rotation keys, services and `alsoKnownAs` entries when the operation is parsed, before
+/// its signature is verified.
+pub fn validate_operation_history_with_limits(
+    binary_did: &[u8],
+    history: Vec<Cursor>,
+    rotation_key_indices: Vec<usize>,
+    msg: &[u8],
+    final_sig: &[u8],
+    limits: &Limits,
+) -> Result<Cid, HistoryError> {
     let history_len = history.len();
 
     if history_len == 0 || (history_len + 1) != rotation_key_indices.len() {
-        return Err(Error::InvalidHistory);
+        return Err(HistoryError::new(
+            0,
+            HistoryStage::History,
+            Error::InvalidHistory,
+        ));
     }
-    let genesis_operation: Vec<u8> = history[0].clone().try_into()?;
+    check_limits(&history, limits, HistoryStage::Genesis)?;
     // Signing key index mapping:
     // - rotation_key_indices[0]: Genesis operation
     // - rotation_key_indices[1]: Transition from operation[0] to operation[1]
     // - ...
     // - rotation_key_indices[history_len - 1]: Transition from operation[history_len-2] to operation[history_len-1]
     // - rotation_key_indices[history_len]: Final operation
-    validate_genesis_operation(&genesis_operation, binary_did, rotation_key_indices[0])?;
-    let mut prev = genesis_operation;
-    for index in 1..history_len {
-        let cur: Vec<u8> = history[index].clone().try_into()?;
-        validate_2_operations(&prev, &cur, rotation_key_indices[index])?;
-        prev = cur;
-    }
+    //
+    // Operations are parsed directly from the cursors, without loading them into memory
+    // as a whole. See `stream.rs`.
+    let last = verify_history(
+        binary_did,
+        history_len,
+        |index| StreamedOperation::from_cursor_with_limits(&history[index], limits),
+        |index, op, rotation_keys| op.verify_signature(rotation_keys, rotation_key_indices[index]),
+    )?;
     // Validate the final operation signature to authorize the did:plc operation on chain
-    validate_final_operation(&prev, final_sig, msg, rotation_key_indices[history_len])?;
+    verify_final(
+        &last,
+        history_len,
+        rotation_key_indices[history_len],
+        msg,
+        final_sig,
+    )?;
+    Ok(last.cid)
+}
+
+/// Validates the operations after a trusted checkpoint and the final authorization
+/// signature, like `validate_operation_history` without verifying the history up to the
+/// checkpoint again.
+///
+/// * `checkpoint` - The CID of an operation verified before, e.g. the head CID stored in
+///   a previous cell
+/// * `history` - The checkpoint operation itself, followed by the operations after it.
+///   It can be the checkpoint operation only.
+/// * `rotation_key_indices` - The length must be `history.len()` where:
+///   - `rotation_key_indices[0..history_len - 1]`: Indices for transitions between
+///     operations, the checkpoint operation is not verified again
+///   - `rotation_key_indices[history_len - 1]`: Index for final authorization signature
+/// * `msg`, `final_sig` - Same as `validate_operation_history`
+///
+/// Returns the CID of the last operation, the next checkpoint. The default `Limits`
+/// apply, see `validate_operation_history_from_checkpoint_with_limits`.
+pub fn validate_operation_history_from_checkpoint(
+    checkpoint: &Cid,
+    history: Vec<Cursor>,
+    rotation_key_indices: Vec<usize>,
+    msg: &[u8],
+    final_sig: &[u8],
+) -> Result<Cid, HistoryError> {
+    validate_operation_history_from_checkpoint_with_limits(
+        checkpoint,
+        history,
+        rotation_key_indices,
+        msg,
+        final_sig,
+        &Limits::default(),
+    )
+}
+
+/// Like `validate_operation_history_from_checkpoint`, with the given `limits`, checked as
+/// in `validate_operation_history_with_limits` except the history length is counted from
+/// the checkpoint.
+pub fn validate_operation_history_from_checkpoint_with_limits(
+    checkpoint: &Cid,
+    history: Vec<Cursor>,
+    rotation_key_indices: Vec<usize>,
+    msg: &[u8],
+    final_sig: &[u8],
+    limits: &Limits,
+) -> Result<Cid, HistoryError> {
+    let history_len = history.len();
+
+    if history_len == 0 || history_len != rotation_key_indices.len() {
+        return Err(HistoryError::new(
+            0,
+            HistoryStage::History,
+            Error::InvalidHistory,
+        ));
+    }
+    check_limits(&history, limits, HistoryStage::Checkpoint)?;
+    let parse = |index| StreamedOperation::from_cursor_with_limits(&history[index], limits);
+    let checkpoint_op = parse(0)
+        .and_then(|op| {
+            if op.cid != *checkpoint {
+                return Err(Error::CheckpointMismatched);
+            }
+            Ok(op)
+        })
+        .map_err(|e| HistoryError::new(0, HistoryStage::Checkpoint, e))?;
+    let last = verify_transitions(checkpoint_op, history_len, parse, |index, op, keys| {
+        op.verify_signature(keys, rotation_key_indices[index - 1])
+    })?;
+    verify_final(
+        &last,
+        history_len,
+        rotation_key_indices[history_len - 1],
+        msg,
+        final_sig,
+    )?;
+    Ok(last.cid)
+}
+
+// History length and operation sizes, before any operation is parsed
+fn check_limits(
+    history: &[Cursor],
+    limits: &Limits,
+    first_stage: HistoryStage,
+) -> Result<(), HistoryError> {
+    limits
+        .check_history_length(history.len())
+        .map_err(|e| HistoryError::new(0, HistoryStage::History, e))?;
+    for (index, op) in history.iter().enumerate() {
+        let stage = if index == 0 {
+            first_stage
+        } else {
+            HistoryStage::Transition
+        };
+        limits
+            .check_operation_size(op.size)
+            .map_err(|e| HistoryError::new(index, stage, e))?;
+    }
     Ok(())
 }
+
+fn verify_final(
+    last: &StreamedOperation,
+    history_len: usize,
+    rotation_key_index: usize,
+    msg: &[u8],
+    final_sig: &[u8],
+) -> Result<(), HistoryError> {
+    last.rotation_keys
+        .get(rotation_key_index)
+        .ok_or(Error::InvalidKeyIndex)
+        .and_then(|key| key.verify(msg, final_sig))
+        .map_err(|e| HistoryError::new(history_len - 1, HistoryStage::Final, e))
+}
+
+/// Validates a complete DID PLC operation history chain and final authorization signature
+/// like `validate_operation_history`, without knowing the rotation key indices.
+/// Every rotation key is tried on each step, and the resulting indices are returned. They
+/// can be used as `rotation_key_indices` of `validate_operation_history` (i.e.
+/// `PlcAuthorization.rotation_key_indices` of the on-chain script), so the default
+/// `Limits` apply as well.
+pub fn find_rotation_key_indices<T: AsRef<[u8]>>(
+    binary_did: &[u8],
+    history: &[T],
+    msg: &[u8],
+    final_sig: &[u8],
+) -> Result<Vec<usize>, Error> {
+    let limits = Limits::default();
+    limits.check_history_length(history.len())?;
+    for op in history {
+        limits.check_operation_size(op.as_ref().len())?;
+    }
+    let mut rotation_key_indices = Vec::with_capacity(history.len() + 1);
+    let last = verify_history(
+        binary_did,
+        history.len(),
+        |index| StreamedOperation::from_slice_with_limits(history[index].as_ref(), &limits),
+        |_, op, rotation_keys| {
+            rotation_key_indices.push(op.find_signing_key(rotation_keys)?);
+            Ok(())
+        },
+    )?;
+    rotation_key_indices.push(find_final_rotation_key_index(&last, final_sig, msg)?);
+    Ok(rotation_key_indices)
+}
@@ -1,12 +1,71 @@
 use crate::error::Error;
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use multibase::Base::Base58Btc;
+use sha2::{Digest, Sha256};
 
-pub(crate) struct PublicKey {
+/// Curves of the keys supported by did:plc, see https://atproto.com/specs/cryptography
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Curve {
+    /// `ES256K`
+    Secp256k1,
+    /// `ES256`, a.k.a. NIST P-256
+    Secp256r1,
+}
+
+impl Curve {
+    // multicodec prefix (varint encoded) of a compressed public key
+    fn multicodec(&self) -> [u8; 2] {
+        match self {
+            Curve::Secp256k1 => SECP256K1_MULTICODEC,
+            Curve::Secp256r1 => SECP256R1_MULTICODEC,
+        }
+    }
+
+    /// `crv` of a JWK
+    pub fn jwk_name(&self) -> &'static str {
+        match self {
+            Curve::Secp256k1 => "secp256k1",
+            Curve::Secp256r1 => "P-256",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
     // compressed public key
     pubkey: Vec<u8>,
-    // if false, it is secp256r1
-    is_secp256k1: bool,
+    curve: Curve,
+}
+
+/// A public key as a JSON Web Key (RFC 7517), coordinates in base64url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    pub y: String,
+}
+
+impl Jwk {
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        crate::json::write_json_object(
+            &mut json,
+            &[
+                ("kty", &self.kty),
+                ("crv", &self.crv),
+                ("x", &self.x),
+                ("y", &self.y),
+            ],
+        );
+        json
+    }
 }
 
 // https://atproto.com/specs/cryptography
@@ -22,49 +81,145 @@ pub fn decode_base58btc(input: &str) -> Result<Vec<u8>, Error> {
     Ok(decoded)
 }
 
+// multicodec prefixes (varint encoded) of compressed public keys
+const SECP256K1_MULTICODEC: [u8; 2] = [0xE7, 0x01];
+const SECP256R1_MULTICODEC: [u8; 2] = [0x80, 0x24];
+const COMPRESSED_LENGTH: usize = 33;
+
 impl PublicKey {
     #[allow(dead_code)]
-    pub(crate) fn raw(&self) -> &[u8] {
-        &self.pubkey
+    pub(crate) fn new(pubkey: Vec<u8>, curve: Curve) -> Self {
+        PublicKey { pubkey, curve }
     }
-    pub(crate) fn from_str(key: &str) -> Result<Self, Error> {
-        if !key.starts_with("did:key:") {
-            return Err(Error::InvalidKey);
-        }
-        let key = key.split_at(8).1;
+
+    /// From a SEC1 encoded point, compressed (33 bytes) or uncompressed (65 bytes).
+    /// The point is checked to be on the curve.
+    pub fn from_sec1_bytes(curve: Curve, bytes: &[u8]) -> Result<Self, Error> {
+        let pubkey = match curve {
+            Curve::Secp256k1 => k256::ecdsa::VerifyingKey::from_sec1_bytes(bytes)
+                .map_err(|_| Error::InvalidKey)?
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+            Curve::Secp256r1 => p256::ecdsa::VerifyingKey::from_sec1_bytes(bytes)
+                .map_err(|_| Error::InvalidKey)?
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+        };
+        Ok(PublicKey { pubkey, curve })
+    }
+
+    /// From `did:key:z...`. Only the encoding is checked, not the point itself, which
+    /// is decoded when a signature is verified.
+    pub fn from_did_key(key: &str) -> Result<Self, Error> {
+        let key = key.strip_prefix("did:key:").ok_or(Error::InvalidKey)?;
+        Self::from_multikey(key)
+    }
+
+    /// From a `publicKeyMultibase` of a `Multikey` verification method, i.e. `z...`.
+    pub fn from_multikey(key: &str) -> Result<Self, Error> {
         let raw_pubkey = decode_base58btc(key)?;
-        let is_secp256k1 = raw_pubkey[0] == 0xE7 && raw_pubkey[1] == 0x01;
-        if !is_secp256k1 && (raw_pubkey[0] != 0x80 || raw_pubkey[1] != 0x24) {
-            return Err(Error::InvalidKey);
-        }
+        let curve = match raw_pubkey.get(..2) {
+            Some(prefix) if prefix == SECP256K1_MULTICODEC => Curve::Secp256k1,
+            Some(prefix) if prefix == SECP256R1_MULTICODEC => Curve::Secp256r1,
+            _ => return Err(Error::InvalidKey),
+        };
         let pubkey = raw_pubkey[2..].to_vec();
-        if pubkey.len() != 33 {
+        if pubkey.len() != COMPRESSED_LENGTH {
             return Err(Error::InvalidKey);
         }
-        Ok(PublicKey {
-            pubkey,
-            is_secp256k1,
+        Ok(PublicKey { pubkey, curve })
+    }
+
+    /// The compressed SEC1 encoded point
+    pub fn raw(&self) -> &[u8] {
+        &self.pubkey
+    }
+
+    pub fn curve(&self) -> Curve {
+        self.curve
+    }
+
+    /// The uncompressed SEC1 encoded point (65 bytes)
+    pub fn to_uncompressed(&self) -> Result<Vec<u8>, Error> {
+        let point = match self.curve {
+            Curve::Secp256k1 => k256::ecdsa::VerifyingKey::from_sec1_bytes(&self.pubkey)
+                .map_err(|_| Error::InvalidKey)?
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+            Curve::Secp256r1 => p256::ecdsa::VerifyingKey::from_sec1_bytes(&self.pubkey)
+                .map_err(|_| Error::InvalidKey)?
+                .to_encoded_point(false)
+                .as_bytes()
+                .to_vec(),
+        };
+        Ok(point)
+    }
+
+    /// `publicKeyMultibase` of a `Multikey` verification method
+    pub fn to_multikey(&self) -> String {
+        let mut raw_pubkey = self.curve.multicodec().to_vec();
+        raw_pubkey.extend_from_slice(&self.pubkey);
+        format!("z{}", Base58Btc.encode(raw_pubkey))
+    }
+
+    pub fn to_did_key(&self) -> String {
+        format!("did:key:{}", self.to_multikey())
+    }
+
+    pub fn to_jwk(&self) -> Result<Jwk, Error> {
+        let point = self.to_uncompressed()?;
+        Ok(Jwk {
+            kty: String::from("EC"),
+            crv: String::from(self.curve.jwk_name()),
+            x: URL_SAFE_NO_PAD.encode(&point[1..33]),
+            y: URL_SAFE_NO_PAD.encode(&point[33..]),
         })
     }
+
+    // https://atproto.com/specs/cryptography
+    // Only low-S signatures are accepted to prevent signature malleability.
     pub(crate) fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), Error> {
-        if self.is_secp256k1 {
-            use k256::ecdsa::signature::Verifier;
+        self.verify_prehash(&Sha256::digest(msg), sig)
+    }
+    // Same as `verify`, with the SHA-256 digest of the message. A malformed signature is
+    // `InvalidSignature`, a well-formed one not matching `VerifySignatureFailed`.
+    pub(crate) fn verify_prehash(&self, digest: &[u8], sig: &[u8]) -> Result<(), Error> {
+        if self.curve == Curve::Secp256k1 {
+            use k256::ecdsa::signature::hazmat::PrehashVerifier;
             let sig =
                 k256::ecdsa::Signature::from_slice(sig).map_err(|_| Error::InvalidSignature)?;
+            if sig.normalize_s().is_some() {
+                return Err(Error::HighSSignature);
+            }
             let pubkey = k256::ecdsa::VerifyingKey::from_sec1_bytes(&self.pubkey)
                 .map_err(|_| Error::InvalidKey)?;
             pubkey
-                .verify(msg, &sig)
-                .map_err(|_| Error::InvalidSignature)
+                .verify_prehash(digest, &sig)
+                .map_err(|_| Error::VerifySignatureFailed)
         } else {
-            use p256::ecdsa::signature::Verifier;
+            use p256::ecdsa::signature::hazmat::PrehashVerifier;
             let sig =
                 p256::ecdsa::Signature::from_slice(sig).map_err(|_| Error::InvalidSignature)?;
+            if sig.normalize_s().is_some() {
+                return Err(Error::HighSSignature);
+            }
             let pubkey = p256::ecdsa::VerifyingKey::from_sec1_bytes(&self.pubkey)
                 .map_err(|_| Error::InvalidKey)?;
             pubkey
-                .verify(msg, &sig)
-                .map_err(|_| Error::InvalidSignature)
+                .verify_prehash(digest, &sig)
+                .map_err(|_| Error::VerifySignatureFailed)
         }
     }
 }
+
+impl core::str::FromStr for PublicKey {
+    type Err = Error;
+
+    /// Same as `PublicKey::from_did_key`
+    fn from_str(key: &str) -> Result<Self, Error> {
+        Self::from_did_key(key)
+    }
+}
@@ -0,0 +1,239 @@
+// serde support, off-chain only.
+//
+// The representation is the JSON of the PLC directory: an operation is its DAG-CBOR map
+// as is (see `Operation::to_json`), CIDs and public keys are strings (`bafy...`,
+// `did:key:...`). Bytes and CID links don't appear in did:plc operations; when they do,
+// they are encoded as in DAG-JSON: `{"/":{"bytes":"<base64>"}}` and `{"/":"<CID>"}`.
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use cbor4ii::core::Value;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::cid::Cid;
+use crate::dag_cbor::{CID_TAG, dag_cbor_map};
+use crate::operation::Operation;
+use crate::pubkey::PublicKey;
+
+impl Serialize for Operation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_map(self.raw(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Operation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = deserializer.deserialize_any(ValueVisitor)?;
+        Operation::from_value(value).map_err(|e| de::Error::custom(format!("{:?}", e)))
+    }
+}
+
+impl Serialize for Cid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(|e| de::Error::custom(format!("{:?}", e)))
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_did_key())
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        PublicKey::from_did_key(&s).map_err(|e| de::Error::custom(format!("{:?}", e)))
+    }
+}
+
+struct SerializeValue<'a>(&'a Value);
+
+impl Serialize for SerializeValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Integer(i) => match i64::try_from(*i) {
+                Ok(i) => serializer.serialize_i64(i),
+                Err(_) => serializer.serialize_i128(*i),
+            },
+            Value::Text(s) => serializer.serialize_str(s),
+            Value::Bytes(bytes) => {
+                let encoded = STANDARD_NO_PAD.encode(bytes);
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("/", &Link("bytes", &encoded))?;
+                map.end()
+            }
+            Value::Tag(CID_TAG, inner) => {
+                // the binary form of a CID link starts with the identity multibase prefix
+                let cid = match &**inner {
+                    Value::Bytes(bytes) if bytes.first() == Some(&0) => {
+                        Cid::from_bytes(&bytes[1..])
+                            .map_err(|e| ser::Error::custom(format!("{:?}", e)))?
+                    }
+                    _ => return Err(ser::Error::custom("invalid CID link")),
+                };
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("/", &cid)?;
+                map.end()
+            }
+            Value::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&SerializeValue(item))?;
+                }
+                seq.end()
+            }
+            Value::Map(pairs) => serialize_map(pairs, serializer),
+            _ => Err(ser::Error::custom("no JSON representation")),
+        }
+    }
+}
+
+// `{"<key>":"<value>"}`
+struct Link<'a>(&'a str, &'a str);
+
+impl Serialize for Link<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.0, self.1)?;
+        map.end()
+    }
+}
+
+fn serialize_map<S: Serializer>(
+    pairs: &[(Value, Value)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(pairs.len()))?;
+    for (key, value) in pairs {
+        let Value::Text(key) = key else {
+            return Err(ser::Error::custom("map key is not a string"));
+        };
+        map.serialize_entry(key, &SerializeValue(value))?;
+    }
+    map.end()
+}
+
+// Maps are converted into DAG-CBOR maps with sorted keys, as `json::from_json` does
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a DAG-JSON value without floats")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Integer(v.into()))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::Text(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element::<DeserializeValue>()? {
+            items.push(item.0);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut pairs: Vec<(Value, Value)> = vec![];
+        while let Some((key, value)) = map.next_entry::<String, DeserializeValue>()? {
+            if pairs
+                .iter()
+                .any(|(k, _)| matches!(k, Value::Text(k) if *k == key))
+            {
+                return Err(de::Error::custom(format!("duplicated key {}", key)));
+            }
+            pairs.push((Value::Text(key), value.0));
+        }
+        match pairs.as_slice() {
+            [(Value::Text(key), link)] if key == "/" => from_link(link),
+            _ => Ok(dag_cbor_map(pairs)),
+        }
+    }
+}
+
+// `{"/":"<CID>"}` or `{"/":{"bytes":"<base64>"}}`
+fn from_link<E: de::Error>(link: &Value) -> Result<Value, E> {
+    match link {
+        Value::Text(cid) => {
+            let cid: Cid = cid
+                .parse()
+                .map_err(|e| de::Error::custom(format!("{:?}", e)))?;
+            let mut bytes = vec![0];
+            bytes.extend_from_slice(&cid.to_bytes());
+            Ok(Value::Tag(CID_TAG, Box::new(Value::Bytes(bytes))))
+        }
+        Value::Map(pairs) => match pairs.as_slice() {
+            [(Value::Text(key), Value::Text(encoded))] if key == "bytes" => STANDARD_NO_PAD
+                .decode(encoded)
+                .map(Value::Bytes)
+                .map_err(|_| de::Error::custom("invalid base64 bytes")),
+            _ => Err(de::Error::custom("invalid link")),
+        },
+        _ => Err(de::Error::custom("invalid link")),
+    }
+}
+
+struct DeserializeValue(Value);
+
+impl<'de> Deserialize<'de> for DeserializeValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer
+            .deserialize_any(ValueVisitor)
+            .map(DeserializeValue)
+    }
+}
@@ -0,0 +1,74 @@
+use crate::error::Error;
+use crate::pubkey::{Curve, PublicKey};
+use alloc::string::String;
+use alloc::vec::Vec;
+use base64::Engine;
+
+/// A rotation key which can sign DID PLC operations.
+pub enum PrivateKey {
+    Secp256k1(k256::ecdsa::SigningKey),
+    Secp256r1(p256::ecdsa::SigningKey),
+}
+
+impl PrivateKey {
+    pub fn secp256k1_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let key = k256::ecdsa::SigningKey::from_slice(bytes).map_err(|_| Error::InvalidKey)?;
+        Ok(PrivateKey::Secp256k1(key))
+    }
+
+    pub fn secp256r1_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let key = p256::ecdsa::SigningKey::from_slice(bytes).map_err(|_| Error::InvalidKey)?;
+        Ok(PrivateKey::Secp256r1(key))
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            PrivateKey::Secp256k1(key) => PublicKey::new(
+                key.verifying_key()
+                    .to_encoded_point(true)
+                    .as_bytes()
+                    .to_vec(),
+                Curve::Secp256k1,
+            ),
+            PrivateKey::Secp256r1(key) => PublicKey::new(
+                key.verifying_key()
+                    .to_encoded_point(true)
+                    .as_bytes()
+                    .to_vec(),
+                Curve::Secp256r1,
+            ),
+        }
+    }
+
+    pub fn did_key(&self) -> String {
+        self.public_key().to_did_key()
+    }
+
+    /// Signs `msg` (hashed with SHA-256) and returns the 64 bytes compact signature.
+    /// The signature is always normalized to low-S as required by
+    /// https://atproto.com/specs/cryptography
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            PrivateKey::Secp256k1(key) => {
+                use k256::ecdsa::signature::Signer;
+                let sig: k256::ecdsa::Signature =
+                    key.try_sign(msg).map_err(|_| Error::InvalidSignature)?;
+                let sig = sig.normalize_s().unwrap_or(sig);
+                Ok(sig.to_bytes().to_vec())
+            }
+            PrivateKey::Secp256r1(key) => {
+                use p256::ecdsa::signature::Signer;
+                let sig: p256::ecdsa::Signature =
+                    key.try_sign(msg).map_err(|_| Error::InvalidSignature)?;
+                let sig = sig.normalize_s().unwrap_or(sig);
+                Ok(sig.to_bytes().to_vec())
+            }
+        }
+    }
+
+    // The `sig` field of an operation: URL-safe base64 without padding
+    pub(crate) fn sign_base64(&self, msg: &[u8]) -> Result<String, Error> {
+        let sig = self.sign(msg)?;
+        Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(sig))
+    }
+}
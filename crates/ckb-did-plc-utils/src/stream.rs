@@ -0,0 +1,656 @@
+// Streaming parser of DAG-CBOR encoded operations, reading directly from a molecule
+// `Cursor` (or a slice).
+//
+// `Operation::from_slice` needs the whole operation in memory and builds a `Value` tree
+// from it. On chain, only `type`, `prev`, the rotation keys and `sig` are needed to
+// verify a history. This parser reads the operation in small chunks, extracts those
+// fields and only checks the shape of the others, while feeding every byte into two
+// hashers at once:
+// * the whole operation, for its CID (and DID of the genesis operation)
+// * the operation without `sig`, for the signature verification
+//
+// The same rules as `Operation::from_slice_strict` and `Operation::validate` apply,
+// with the same errors. `Limits` are checked on top of them, on the size of the
+// operation and on the array/map headers before their items are read.
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use molecule::lazy_reader::Cursor;
+use sha2::{Digest, Sha256};
+
+use crate::cid::Cid;
+use crate::dag_cbor::{CID_TAG, MAX_DEPTH};
+use crate::error::Error;
+use crate::limits::Limits;
+use crate::operation::{MAX_ROTATION_KEYS, OperationType, decode_signature};
+use crate::pubkey::PublicKey;
+
+const CHUNK_SIZE: usize = 256;
+
+/// The fields of an operation needed to verify a history.
+pub struct StreamedOperation {
+    pub r#type: OperationType,
+    pub prev: Option<Cid>,
+    /// `rotationKeys`, or `signingKey` and `recoveryKey` of a legacy operation in map
+    /// order, which is not their priority: `recoveryKey` comes first once normalized.
+    /// Empty for tombstone.
+    pub rotation_keys: Vec<PublicKey>,
+    pub cid: Cid,
+    sig: String,
+    // SHA-256 of the operation without `sig`
+    unsigned_digest: [u8; 32],
+}
+
+impl StreamedOperation {
+    pub fn from_cursor(cursor: &Cursor) -> Result<Self, Error> {
+        Self::parse(cursor, &Limits::UNLIMITED)
+    }
+
+    pub fn from_slice(buf: &[u8]) -> Result<Self, Error> {
+        Self::parse(buf, &Limits::UNLIMITED)
+    }
+
+    /// Like `from_cursor`, rejecting an operation beyond `limits` before reading it as a
+    /// whole.
+    pub fn from_cursor_with_limits(cursor: &Cursor, limits: &Limits) -> Result<Self, Error> {
+        Self::parse(cursor, limits)
+    }
+
+    pub fn from_slice_with_limits(buf: &[u8], limits: &Limits) -> Result<Self, Error> {
+        Self::parse(buf, limits)
+    }
+
+    fn parse<S: Source + ?Sized>(source: &S, limits: &Limits) -> Result<Self, Error> {
+        limits.check_operation_size(source.size())?;
+        let mut reader = Reader::new(source, limits);
+        let fields = reader.operation()?;
+        if reader.pos != source.size() {
+            return Err(Error::NonCanonicalCbor);
+        }
+        let cid = Cid::new(reader.hasher.finalize().into());
+        let unsigned_digest = reader.unsigned_hasher.finalize().into();
+        fields.into_operation(cid, unsigned_digest)
+    }
+
+    pub fn is_tombstone(&self) -> bool {
+        self.r#type == OperationType::PlcTombstone
+    }
+
+    /// The binary DID, if this is the genesis operation.
+    pub fn binary_did(&self) -> &[u8] {
+        &self.cid.digest()[..15]
+    }
+
+    pub fn get_signature(&self) -> Result<Vec<u8>, Error> {
+        decode_signature(&self.sig)
+    }
+
+    // note, the `pubkeys` are from previous operation
+    pub(crate) fn verify_signature(
+        &self,
+        pubkeys: &[PublicKey],
+        rotation_key_index: usize,
+    ) -> Result<(), Error> {
+        let sig = self.get_signature()?;
+        let pubkey = pubkeys
+            .get(rotation_key_index)
+            .ok_or(Error::InvalidKeyIndex)?;
+        match pubkey.verify_prehash(&self.unsigned_digest, &sig) {
+            Ok(()) => Ok(()),
+            Err(Error::HighSSignature) => Err(Error::HighSSignature),
+            Err(_) => {
+                #[cfg(feature = "enable_log")]
+                {
+                    log::warn!("verify signature failed");
+                    log::warn!("sig: (length = {}), {}", sig.len(), hex::encode(&sig));
+                    log::warn!("rotation_key_index = {}", rotation_key_index);
+                }
+                Err(Error::VerifySignatureFailed)
+            }
+        }
+    }
+
+    // Tries every key of `pubkeys` in order, returns the index of the first one that
+    // verifies the signature.
+    pub(crate) fn find_signing_key(&self, pubkeys: &[PublicKey]) -> Result<usize, Error> {
+        for index in 0..pubkeys.len() {
+            match self.verify_signature(pubkeys, index) {
+                Ok(()) => return Ok(index),
+                Err(Error::VerifySignatureFailed) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::VerifySignatureFailed)
+    }
+}
+
+// A value of a known field: missing, of the wrong shape, or extracted
+#[derive(Default)]
+enum Field<T> {
+    #[default]
+    Missing,
+    Invalid,
+    Valid(T),
+}
+
+impl<T> Field<T> {
+    fn is_present(&self) -> bool {
+        !matches!(self, Field::Missing)
+    }
+
+    fn discard(self) -> Field<()> {
+        match self {
+            Field::Missing => Field::Missing,
+            Field::Invalid => Field::Invalid,
+            Field::Valid(_) => Field::Valid(()),
+        }
+    }
+
+    fn valid(self, error: Error) -> Result<T, Error> {
+        match self {
+            Field::Valid(value) => Ok(value),
+            _ => Err(error),
+        }
+    }
+}
+
+// Shape only, the content is not needed
+#[derive(Default)]
+struct Fields {
+    r#type: Field<String>,
+    prev: Field<Option<String>>,
+    sig: Field<String>,
+    rotation_keys: Field<Vec<String>>,
+    signing_key: Field<String>,
+    recovery_key: Field<String>,
+    verification_methods: Field<()>,
+    also_known_as: Field<()>,
+    services: Field<()>,
+    handle: Field<()>,
+    service: Field<()>,
+    unexpected: bool,
+}
+
+impl Fields {
+    fn is_present(&self, key: &str) -> bool {
+        match key {
+            "type" => self.r#type.is_present(),
+            "prev" => self.prev.is_present(),
+            "sig" => self.sig.is_present(),
+            "rotationKeys" => self.rotation_keys.is_present(),
+            "signingKey" => self.signing_key.is_present(),
+            "recoveryKey" => self.recovery_key.is_present(),
+            "verificationMethods" => self.verification_methods.is_present(),
+            "alsoKnownAs" => self.also_known_as.is_present(),
+            "services" => self.services.is_present(),
+            "handle" => self.handle.is_present(),
+            "service" => self.service.is_present(),
+            _ => false,
+        }
+    }
+
+    // Same checks in the same order as `Operation::validate`
+    fn into_operation(
+        self,
+        cid: Cid,
+        unsigned_digest: [u8; 32],
+    ) -> Result<StreamedOperation, Error> {
+        let r#type = match &self.r#type {
+            Field::Valid(r#type) => OperationType::parse(r#type)?,
+            _ => return Err(Error::InvalidOperation),
+        };
+        let fields = r#type.fields();
+        if !fields.iter().all(|key| self.is_present(key)) {
+            return Err(Error::MissingField);
+        }
+        const ALL_FIELDS: [&str; 11] = [
+            "type",
+            "prev",
+            "sig",
+            "rotationKeys",
+            "signingKey",
+            "recoveryKey",
+            "verificationMethods",
+            "alsoKnownAs",
+            "services",
+            "handle",
+            "service",
+        ];
+        if self.unexpected
+            || ALL_FIELDS
+                .iter()
+                .any(|key| self.is_present(key) && !fields.contains(key))
+        {
+            return Err(Error::UnexpectedField);
+        }
+        let sig = self.sig.valid(Error::InvalidSignature)?;
+        let prev = match (r#type, self.prev) {
+            // legacy operation only exists as genesis operation
+            (OperationType::Create, Field::Valid(None)) => None,
+            (OperationType::PlcOperation, Field::Valid(prev)) => prev,
+            (OperationType::PlcTombstone, Field::Valid(Some(prev))) => Some(prev),
+            _ => return Err(Error::InvalidPrevType),
+        };
+        let prev = prev.map(|prev| prev.parse::<Cid>()).transpose()?;
+        let rotation_keys = match r#type {
+            OperationType::Create => {
+                let mut rotation_keys = vec![];
+                // map order
+                for key in [self.signing_key, self.recovery_key] {
+                    if let Field::Valid(key) = key {
+                        rotation_keys.push(PublicKey::from_did_key(&key)?);
+                    }
+                }
+                if rotation_keys.len() != 2 {
+                    return Err(Error::InvalidKey);
+                }
+                self.handle.valid(Error::InvalidAlsoKnownAs)?;
+                self.service.valid(Error::InvalidServices)?;
+                rotation_keys
+            }
+            OperationType::PlcOperation => {
+                let keys = self.rotation_keys.valid(Error::RotationKeysDecodeError)?;
+                if keys.is_empty() || keys.len() > MAX_ROTATION_KEYS {
+                    return Err(Error::InvalidRotationKeysCount);
+                }
+                let mut rotation_keys = vec![];
+                for (index, key) in keys.iter().enumerate() {
+                    rotation_keys.push(PublicKey::from_did_key(key)?);
+                    if keys[..index].contains(key) {
+                        return Err(Error::DuplicatedRotationKey);
+                    }
+                }
+                self.verification_methods
+                    .valid(Error::InvalidVerificationMethods)?;
+                self.also_known_as.valid(Error::InvalidAlsoKnownAs)?;
+                self.services.valid(Error::InvalidServices)?;
+                rotation_keys
+            }
+            OperationType::PlcTombstone => vec![],
+        };
+        Ok(StreamedOperation {
+            r#type,
+            prev,
+            rotation_keys,
+            cid,
+            sig,
+            unsigned_digest,
+        })
+    }
+}
+
+// Where an operation is read from
+trait Source {
+    fn size(&self) -> usize;
+    // Reads from `offset` into `buf`, returns the number of bytes read
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, Error>;
+}
+
+impl Source for Cursor {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut cursor = self.clone();
+        cursor.add_offset(offset)?;
+        cursor.sub_size(offset)?;
+        Ok(cursor.read_at(buf)?)
+    }
+}
+
+impl Source for [u8] {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = buf.len().min(self.len() - offset);
+        buf[..len].copy_from_slice(&self[offset..offset + len]);
+        Ok(len)
+    }
+}
+
+struct Reader<'a, S: Source + ?Sized> {
+    source: &'a S,
+    limits: &'a Limits,
+    // number of bytes consumed
+    pos: usize,
+    chunk: [u8; CHUNK_SIZE],
+    chunk_start: usize,
+    chunk_len: usize,
+    hasher: Sha256,
+    unsigned_hasher: Sha256,
+}
+
+impl<'a, S: Source + ?Sized> Reader<'a, S> {
+    fn new(source: &'a S, limits: &'a Limits) -> Self {
+        Reader {
+            source,
+            limits,
+            pos: 0,
+            chunk: [0u8; CHUNK_SIZE],
+            chunk_start: 0,
+            chunk_len: 0,
+            hasher: Sha256::new(),
+            unsigned_hasher: Sha256::new(),
+        }
+    }
+
+    // Reads the next bytes into `buf`, feeding the hashers
+    fn read(&mut self, mut buf: &mut [u8]) -> Result<(), Error> {
+        if buf.len() > self.source.size() - self.pos {
+            return Err(Error::InvalidCbor);
+        }
+        while !buf.is_empty() {
+            if self.pos == self.chunk_start + self.chunk_len {
+                self.chunk_start = self.pos;
+                self.chunk_len = self.source.read_at(self.pos, &mut self.chunk)?;
+                if self.chunk_len == 0 {
+                    return Err(Error::InvalidCbor);
+                }
+            }
+            let start = self.pos - self.chunk_start;
+            let len = buf.len().min(self.chunk_len - start);
+            let bytes = &self.chunk[start..start + len];
+            self.hasher.update(bytes);
+            self.unsigned_hasher.update(bytes);
+            buf[..len].copy_from_slice(bytes);
+            buf = &mut buf[len..];
+            self.pos += len;
+        }
+        Ok(())
+    }
+
+    fn read_vec(&mut self, len: u64) -> Result<Vec<u8>, Error> {
+        if len > (self.source.size() - self.pos) as u64 {
+            return Err(Error::InvalidCbor);
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.read(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_text(&mut self, len: u64) -> Result<String, Error> {
+        String::from_utf8(self.read_vec(len)?).map_err(|_| Error::InvalidCbor)
+    }
+
+    // returns (major type, additional info, argument), see `dag_cbor::Checker::header`
+    fn header(&mut self) -> Result<(u8, u8, u64), Error> {
+        let mut byte = [0u8; 1];
+        self.read(&mut byte)?;
+        let major = byte[0] >> 5;
+        let info = byte[0] & 0x1f;
+        let (len, min) = match info {
+            0..=23 => (0, 0),
+            24 => (1, 24),
+            25 => (2, 0x100),
+            26 => (4, 0x1_0000),
+            27 => (8, 0x1_0000_0000),
+            // indefinite length
+            31 => return Err(Error::NonCanonicalCbor),
+            _ => return Err(Error::InvalidCbor),
+        };
+        let arg = if len == 0 {
+            info as u64
+        } else {
+            let mut bytes = [0u8; 8];
+            self.read(&mut bytes[8 - len..])?;
+            u64::from_be_bytes(bytes)
+        };
+        // major type 7 carries floats/simple values instead of integers
+        if major != 7 && arg < min {
+            return Err(Error::NonCanonicalCbor);
+        }
+        Ok((major, info, arg))
+    }
+
+    // Reads a map key, which must be greater than `last_key` in DAG-CBOR order
+    fn map_key(&mut self, last_key: Option<&str>) -> Result<String, Error> {
+        let (major, _, len) = self.header()?;
+        if major != 3 {
+            return Err(Error::NonCanonicalCbor);
+        }
+        let key = self.read_text(len)?;
+        if let Some(last_key) = last_key {
+            // strictly increasing, which also rejects duplicated keys
+            if (last_key.len(), last_key.as_bytes()) >= (key.len(), key.as_bytes()) {
+                return Err(Error::NonCanonicalCbor);
+            }
+        }
+        Ok(key)
+    }
+
+    // Skips the rest of an item after its header, checking it's canonical
+    fn skip(&mut self, (major, info, arg): (u8, u8, u64), depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidCbor);
+        }
+        match major {
+            // unsigned and negative integers
+            0 | 1 => {}
+            // bytes
+            2 => {
+                self.read_vec(arg)?;
+            }
+            // text
+            3 => {
+                self.read_text(arg)?;
+            }
+            // array
+            4 => {
+                for _ in 0..arg {
+                    let header = self.header()?;
+                    self.skip(header, depth + 1)?;
+                }
+            }
+            // map
+            5 => {
+                let mut last_key = None;
+                for _ in 0..arg {
+                    let key = self.map_key(last_key.as_deref())?;
+                    last_key = Some(key);
+                    let header = self.header()?;
+                    self.skip(header, depth + 1)?;
+                }
+            }
+            // tag
+            6 => {
+                if arg != CID_TAG {
+                    return Err(Error::NonCanonicalCbor);
+                }
+                // a CID, see `dag_cbor::Checker::item`
+                let (major, _, len) = self.header()?;
+                if major != 2 || self.read_vec(len)?.first() != Some(&0) {
+                    return Err(Error::NonCanonicalCbor);
+                }
+            }
+            // false, true, null
+            _ => {
+                if !matches!(info, 20..=22) {
+                    return Err(Error::NonCanonicalCbor);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, depth: usize) -> Result<Field<String>, Error> {
+        let header = self.header()?;
+        if header.0 == 3 {
+            return Ok(Field::Valid(self.read_text(header.2)?));
+        }
+        self.skip(header, depth)?;
+        Ok(Field::Invalid)
+    }
+
+    // Array of text, `check` is called on the number of items before reading them
+    fn text_array(
+        &mut self,
+        depth: usize,
+        check: impl FnOnce(u64) -> Result<(), Error>,
+    ) -> Result<Field<Vec<String>>, Error> {
+        let header = self.header()?;
+        if header.0 != 4 {
+            self.skip(header, depth)?;
+            return Ok(Field::Invalid);
+        }
+        check(header.2)?;
+        let mut items = vec![];
+        let mut valid = true;
+        for _ in 0..header.2 {
+            match self.text(depth + 1)? {
+                Field::Valid(item) => items.push(item),
+                _ => valid = false,
+            }
+        }
+        Ok(if valid {
+            Field::Valid(items)
+        } else {
+            Field::Invalid
+        })
+    }
+
+    // Map of text keys, calling `value` on each value. `check` is called on the number
+    // of entries before reading them
+    fn map<F>(
+        &mut self,
+        depth: usize,
+        check: impl FnOnce(u64) -> Result<(), Error>,
+        mut value: F,
+    ) -> Result<Field<()>, Error>
+    where
+        F: FnMut(&mut Self, &str) -> Result<bool, Error>,
+    {
+        let header = self.header()?;
+        if header.0 != 5 {
+            self.skip(header, depth)?;
+            return Ok(Field::Invalid);
+        }
+        check(header.2)?;
+        let mut last_key = None;
+        let mut valid = true;
+        for _ in 0..header.2 {
+            let key = self.map_key(last_key.as_deref())?;
+            valid &= value(self, &key)?;
+            last_key = Some(key);
+        }
+        Ok(if valid {
+            Field::Valid(())
+        } else {
+            Field::Invalid
+        })
+    }
+
+    fn operation(&mut self) -> Result<Fields, Error> {
+        let (major, _, count) = self.header()?;
+        if major != 5 {
+            return Err(Error::InvalidOperation);
+        }
+        // the unsigned operation has one entry less, re-encode its header
+        self.unsigned_hasher = Sha256::new();
+        self.unsigned_hasher
+            .update(map_header(count.saturating_sub(1)));
+
+        let limits = self.limits;
+        let mut fields = Fields::default();
+        let mut last_key: Option<String> = None;
+        for _ in 0..count {
+            // the `sig` entry is left out of the unsigned operation
+            let unsigned_hasher = self.unsigned_hasher.clone();
+            let key = self.map_key(last_key.as_deref())?;
+            match key.as_str() {
+                "type" => fields.r#type = self.text(1)?,
+                "sig" => fields.sig = self.text(1)?,
+                "prev" => {
+                    let header = self.header()?;
+                    fields.prev = match header {
+                        (3, _, len) => Field::Valid(Some(self.read_text(len)?)),
+                        // null
+                        (7, 22, _) => Field::Valid(None),
+                        _ => {
+                            self.skip(header, 1)?;
+                            Field::Invalid
+                        }
+                    };
+                }
+                "rotationKeys" => {
+                    fields.rotation_keys =
+                        self.text_array(1, |count| limits.check_rotation_keys(count))?;
+                }
+                "signingKey" => fields.signing_key = self.text(1)?,
+                "recoveryKey" => fields.recovery_key = self.text(1)?,
+                "verificationMethods" => {
+                    fields.verification_methods = self.map(1, no_limit, |reader, _| {
+                        Ok(matches!(reader.text(2)?, Field::Valid(key) if key.starts_with("did:key:")))
+                    })?;
+                }
+                "alsoKnownAs" => {
+                    fields.also_known_as = self
+                        .text_array(1, |count| limits.check_also_known_as(count))?
+                        .discard();
+                }
+                "services" => {
+                    let check = |count| limits.check_services(count);
+                    fields.services = self.map(1, check, |reader, _| {
+                        let mut r#type = false;
+                        let mut endpoint = false;
+                        let valid = reader.map(2, no_limit, |reader, key| {
+                            let valid = matches!(reader.text(3)?, Field::Valid(_));
+                            match key {
+                                "type" => r#type = valid,
+                                "endpoint" => endpoint = valid,
+                                _ => return Ok(false),
+                            }
+                            Ok(valid)
+                        })?;
+                        Ok(matches!(valid, Field::Valid(_)) && r#type && endpoint)
+                    })?;
+                }
+                "handle" => {
+                    fields.handle = self.text(1)?.discard();
+                }
+                "service" => {
+                    fields.service = self.text(1)?.discard();
+                }
+                _ => {
+                    fields.unexpected = true;
+                    let header = self.header()?;
+                    self.skip(header, 1)?;
+                }
+            }
+            if key == "sig" {
+                self.unsigned_hasher = unsigned_hasher;
+            }
+            last_key = Some(key);
+        }
+        Ok(fields)
+    }
+}
+
+fn no_limit(_count: u64) -> Result<(), Error> {
+    Ok(())
+}
+
+// Header of a map with `count` entries, in the shortest form
+fn map_header(count: u64) -> Vec<u8> {
+    let mut header = vec![];
+    match count {
+        0..=23 => header.push(0xa0 | count as u8),
+        24..=0xff => header.extend_from_slice(&[0xb8, count as u8]),
+        0x100..=0xffff => {
+            header.push(0xb9);
+            header.extend_from_slice(&(count as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            header.push(0xba);
+            header.extend_from_slice(&(count as u32).to_be_bytes());
+        }
+        _ => {
+            header.push(0xbb);
+            header.extend_from_slice(&count.to_be_bytes());
+        }
+    }
+    header
+}
@@ -0,0 +1,163 @@
+// Incremental verification of a history, one operation at a time.
+//
+// `validate_operation_history` verifies a whole history at once. An indexer following
+// the PLC directory receives the operations one by one instead: `HistoryVerifier` keeps
+// the head of the history (the last operation verified) and verifies each new operation
+// against it only. Its state can be saved with `to_bytes` and resumed with `from_bytes`.
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use cbor4ii::core::Value;
+use cbor4ii::core::dec::Decode;
+use cbor4ii::core::enc::Encode;
+use cbor4ii::core::utils::{BufWriter, SliceReader};
+
+use crate::cid::Cid;
+use crate::dag_cbor::dag_cbor_map;
+use crate::did::PlcDid;
+use crate::document::DidDocument;
+use crate::error::Error;
+use crate::limits::Limits;
+use crate::operation::{Operation, check_binary_did, genesis_operation, next_operation};
+use crate::pubkey::PublicKey;
+use crate::stream::StreamedOperation;
+
+pub struct HistoryVerifier {
+    did: PlcDid,
+    // number of operations verified, genesis included
+    len: usize,
+    // the last operation verified
+    head: Vec<u8>,
+    parsed_head: StreamedOperation,
+    // index of the rotation key which signed the head
+    key_index: usize,
+}
+
+impl HistoryVerifier {
+    /// Starts a history from its genesis operation, which must match `binary_did`.
+    pub fn new(binary_did: &[u8], genesis: &[u8]) -> Result<Self, Error> {
+        let op = genesis_operation(parse(genesis)?)?;
+        let key_index = op.find_signing_key(&op.rotation_keys)?;
+        check_binary_did(&op, binary_did)?;
+        Ok(HistoryVerifier {
+            did: PlcDid::from_bytes(binary_did)?,
+            len: 1,
+            head: genesis.to_vec(),
+            parsed_head: op,
+            key_index,
+        })
+    }
+
+    /// Verifies `op` as the next operation of the history, signed by a rotation key of
+    /// the head. On success, it becomes the new head; the index of the rotation key
+    /// which signed it and its CID are returned. On failure, the state is unchanged.
+    pub fn push(&mut self, op: &[u8]) -> Result<(usize, Cid), Error> {
+        let cur = next_operation(&self.parsed_head, || parse(op))?;
+        let key_index = cur.find_signing_key(&self.parsed_head.rotation_keys)?;
+        let cid = cur.cid;
+        self.len += 1;
+        self.head = op.to_vec();
+        self.parsed_head = cur;
+        self.key_index = key_index;
+        Ok((key_index, cid))
+    }
+
+    pub fn binary_did(&self) -> &[u8] {
+        self.did.as_bytes()
+    }
+
+    /// e.g. `did:plc:...`
+    pub fn did(&self) -> String {
+        format!("{}", self.did)
+    }
+
+    /// Number of operations verified, genesis included.
+    pub fn history_len(&self) -> usize {
+        self.len
+    }
+
+    /// The last operation verified, in DAG-CBOR.
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+
+    pub fn head_cid(&self) -> Cid {
+        self.parsed_head.cid
+    }
+
+    /// Index of the rotation key which signed the head, in the rotation keys of the
+    /// operation before it (its own for genesis).
+    pub fn key_index(&self) -> usize {
+        self.key_index
+    }
+
+    /// The rotation keys which can sign the next operation. Empty once tombstoned.
+    pub fn rotation_keys(&self) -> &[PublicKey] {
+        &self.parsed_head.rotation_keys
+    }
+
+    pub fn is_tombstoned(&self) -> bool {
+        self.parsed_head.is_tombstone()
+    }
+
+    /// The current DID document, `Error::DidTombstoned` once tombstoned.
+    pub fn document(&self) -> Result<DidDocument, Error> {
+        DidDocument::from_operation(&self.did(), &Operation::from_slice(&self.head)?)
+    }
+
+    /// Serializes the state as a DAG-CBOR map.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let state = dag_cbor_map(vec![
+            (text("did"), Value::Bytes(self.did.as_bytes().to_vec())),
+            (text("len"), Value::Integer(self.len as i128)),
+            (text("head"), Value::Bytes(self.head.clone())),
+            (text("keyIndex"), Value::Integer(self.key_index as i128)),
+        ]);
+        let mut writer = BufWriter::new(Vec::new());
+        state.encode(&mut writer).map_err(|_| Error::InvalidCbor)?;
+        Ok(writer.into_inner())
+    }
+
+    /// Resumes from a state serialized by `to_bytes`. The state is trusted: only the
+    /// head operation is parsed again, not the history before it.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, Error> {
+        let state = Value::decode(&mut SliceReader::new(buf)).map_err(|_| Error::InvalidCbor)?;
+        let Value::Map(pairs) = state else {
+            return Err(Error::InvalidCbor);
+        };
+        let field = |key: &str| {
+            pairs
+                .iter()
+                .find(|(k, _)| matches!(k, Value::Text(k) if k == key))
+                .map(|(_, v)| v)
+                .ok_or(Error::MissingField)
+        };
+        let integer = |key: &str| match field(key)? {
+            Value::Integer(i) => usize::try_from(*i).map_err(|_| Error::InvalidCbor),
+            _ => Err(Error::InvalidCbor),
+        };
+        let bytes = |key: &str| match field(key)? {
+            Value::Bytes(bytes) => Ok(bytes.clone()),
+            _ => Err(Error::InvalidCbor),
+        };
+        let head = bytes("head")?;
+        Ok(HistoryVerifier {
+            did: PlcDid::from_bytes(&bytes("did")?)?,
+            len: integer("len")?,
+            parsed_head: parse(&head)?,
+            head,
+            key_index: integer("keyIndex")?,
+        })
+    }
+}
+
+// The per-operation limits apply. The history length isn't limited: the PLC directory
+// doesn't limit it.
+fn parse(op: &[u8]) -> Result<StreamedOperation, Error> {
+    StreamedOperation::from_slice_with_limits(op, &Limits::default())
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(String::from(s))
+}
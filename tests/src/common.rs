@@ -0,0 +1,58 @@
+// CBOR helpers to build and modify operations in tests
+use ckb_did_plc_utils::{
+    base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD},
+    cbor4ii::core::{
+        Value,
+        dec::Decode,
+        enc::Encode,
+        utils::{BufWriter, SliceReader},
+    },
+    signer::PrivateKey,
+};
+
+pub fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut writer = BufWriter::new(Vec::new());
+    value.encode(&mut writer).unwrap();
+    writer.into_inner()
+}
+
+/// The top-level map of an encoded operation
+pub fn decode_map(buf: &[u8]) -> Vec<(Value, Value)> {
+    match Value::decode(&mut SliceReader::new(buf)).unwrap() {
+        Value::Map(pairs) => pairs,
+        _ => panic!("not a map"),
+    }
+}
+
+/// Replaces the value of `key` in place, or appends it if missing
+pub fn set_field(pairs: &mut Vec<(Value, Value)>, key: &str, value: Value) {
+    for (k, v) in pairs.iter_mut() {
+        if *k == text(key) {
+            *v = value;
+            return;
+        }
+    }
+    pairs.push((text(key), value));
+}
+
+/// Sorts text keys in DAG-CBOR order: shorter first, then bytewise
+pub fn sort_keys(pairs: &mut [(Value, Value)]) {
+    pairs.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Value::Text(a), Value::Text(b)) => (a.len(), a).cmp(&(b.len(), b)),
+        _ => unreachable!(),
+    });
+}
+
+/// Signs an unsigned operation with `key` and encodes it with `sig`, keys in DAG-CBOR
+/// order
+pub fn sign_map(mut pairs: Vec<(Value, Value)>, key: &PrivateKey) -> Vec<u8> {
+    sort_keys(&mut pairs);
+    let sig = key.sign(&encode(&Value::Map(pairs.clone()))).unwrap();
+    pairs.push((text("sig"), text(&URL_SAFE_NO_PAD.encode(sig))));
+    sort_keys(&mut pairs);
+    encode(&Value::Map(pairs))
+}
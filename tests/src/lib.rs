@@ -1 +1,21 @@
+pub mod common;
+pub mod test_authorization;
+pub mod test_builder;
+pub mod test_checkpoint;
+pub mod test_cid;
+pub mod test_dag_cbor;
+pub mod test_did;
+pub mod test_diff;
+pub mod test_document;
+pub mod test_fork;
+pub mod test_json;
+pub mod test_key_index;
+pub mod test_legacy;
+pub mod test_limits;
+pub mod test_local_id;
+pub mod test_pubkey;
+pub mod test_schema;
+pub mod test_serde;
+pub mod test_stream;
 pub mod test_vectors;
+pub mod test_verifier;
@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::secp256r1_key;
+use crate::test_key_index::{build_history, to_cursors};
+use ckb_did_plc_utils::{
+    authorization::{AUTHORIZATION_PREFIX, authorization_message},
+    base32,
+    error::{Error, HistoryError},
+    operation::{Operation, validate_operation_history},
+};
+
+fn from_hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_authorization_message() {
+    // the local id extension example of ts-tests
+    let binary_did = base32::decode(
+        base32::Alphabet::Rfc4648Lower { padding: false },
+        "bxvfvvygwbcnbmknn73t6pbu",
+    )
+    .unwrap();
+    let type_id = from_hex("8434cfe81aa825c275d513eee20e4235294e3420");
+    let tx_hash = from_hex("cb86447276a89c20276a667ada34640341e9e9ea47b68548241a934ff49e9fea");
+    let msg = authorization_message(&binary_did, &type_id, &tx_hash);
+    assert_eq!(
+        String::from_utf8(msg).unwrap(),
+        "did:web5 local id authorization\n\
+         did:plc:bxvfvvygwbcnbmknn73t6pbu\n\
+         did:web5:qq2m72a2vas4e5ovcpxoedscguuu4nba\n\
+         tx hash: 0xcb86447276a89c20276a667ada34640341e9e9ea47b68548241a934ff49e9fea"
+    );
+    assert!(
+        authorization_message(&binary_did, &type_id, &tx_hash)
+            .starts_with(AUTHORIZATION_PREFIX.as_bytes())
+    );
+}
+
+#[test]
+fn test_authorization_signature() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let type_id = [0x11u8; 20];
+    let tx_hash = [0x22u8; 32];
+    let msg = authorization_message(&binary_did, &type_id, &tx_hash);
+    let validate = |final_sig: &[u8]| {
+        validate_operation_history(
+            &binary_did,
+            to_cursors(&history),
+            vec![0, 1, 0, 1],
+            &msg,
+            final_sig,
+        )
+    };
+
+    assert!(validate(&secp256r1_key().sign(&msg).unwrap()).is_ok());
+    // a signature over the raw tx hash is not an authorization
+    assert!(matches!(
+        validate(&secp256r1_key().sign(&tx_hash).unwrap()),
+        Err(HistoryError {
+            error: Error::VerifySignatureFailed,
+            ..
+        })
+    ));
+    // nor one for another cell
+    let other = authorization_message(&binary_did, &[0x12u8; 20], &tx_hash);
+    assert!(matches!(
+        validate(&secp256r1_key().sign(&other).unwrap()),
+        Err(HistoryError {
+            error: Error::VerifySignatureFailed,
+            ..
+        })
+    ));
+}
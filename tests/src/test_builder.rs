@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::common::{decode_map, encode, set_field, text};
+use ckb_did_plc_utils::{
+    base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD},
+    builder::OperationBuilder,
+    cbor4ii::core::Value,
+    error::Error,
+    operation::{Operation, OperationType, validate_2_operations, validate_genesis_operation},
+    signer::PrivateKey,
+};
+
+pub fn secp256k1_key(index: u8) -> PrivateKey {
+    let mut bytes: Vec<u8> = (1..=32).collect();
+    bytes[0] += index;
+    PrivateKey::secp256k1_from_bytes(&bytes).unwrap()
+}
+
+pub fn secp256r1_key() -> PrivateKey {
+    let bytes: Vec<u8> = (0x21..=0x40).collect();
+    PrivateKey::secp256r1_from_bytes(&bytes).unwrap()
+}
+
+pub fn genesis_operation(rotation_key1: &PrivateKey, rotation_key2: &PrivateKey) -> Operation {
+    let signing_key = secp256k1_key(0);
+    OperationBuilder::new()
+        .rotation_keys(vec![rotation_key1.did_key(), rotation_key2.did_key()])
+        .verification_method("atproto", &signing_key.did_key())
+        .also_known_as(vec!["at://alice.example.com".to_string()])
+        .service(
+            "atproto_pds",
+            "AtprotoPersonalDataServer",
+            "https://example.com",
+        )
+        .sign(rotation_key1)
+        .unwrap()
+}
+
+#[test]
+fn test_builder_genesis() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let op = genesis_operation(&rotation_key1, &rotation_key2);
+    let buf = op.to_vec().unwrap();
+
+    // round trip
+    let decoded = Operation::from_slice(&buf).unwrap();
+    assert_eq!(decoded.to_vec().unwrap(), buf);
+    assert_eq!(decoded.get_type().unwrap(), OperationType::PlcOperation);
+    assert_eq!(decoded.get_prev().unwrap(), None);
+
+    let binary_did = op.get_binary_did().unwrap();
+    assert!(validate_genesis_operation(&buf, &binary_did, 0).is_ok());
+    assert!(matches!(
+        validate_genesis_operation(&buf, &binary_did, 1),
+        Err(Error::VerifySignatureFailed)
+    ));
+}
+
+#[test]
+fn test_builder_update_and_tombstone() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+
+    // signed by the p256 key at index 1
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .also_known_as(vec!["at://alice.example2.com".to_string()])
+        .sign(&rotation_key2)
+        .unwrap();
+    assert_eq!(
+        update.get_also_known_as().unwrap(),
+        vec!["at://alice.example2.com"]
+    );
+    assert_eq!(
+        update.get_services().unwrap(),
+        genesis.get_services().unwrap()
+    );
+    let genesis_buf = genesis.to_vec().unwrap();
+    let update_buf = update.to_vec().unwrap();
+    assert!(validate_2_operations(&genesis_buf, &update_buf, 1).is_ok());
+    assert!(matches!(
+        validate_2_operations(&genesis_buf, &update_buf, 0),
+        Err(Error::VerifySignatureFailed)
+    ));
+
+    let tombstone = OperationBuilder::tombstone(&update)
+        .unwrap()
+        .sign(&rotation_key1)
+        .unwrap();
+    assert_eq!(tombstone.get_type().unwrap(), OperationType::PlcTombstone);
+    let tombstone_buf = tombstone.to_vec().unwrap();
+    assert!(validate_2_operations(&update_buf, &tombstone_buf, 0).is_ok());
+    assert!(matches!(
+        OperationBuilder::tombstone(&tombstone),
+        Err(Error::DidTombstoned)
+    ));
+}
+
+#[test]
+fn test_builder_low_s_signature() {
+    let key = secp256r1_key();
+    for i in 0..32u8 {
+        let sig = key.sign(&[i; 32]).unwrap();
+        let s = p256_s(&sig);
+        assert!(s <= P256_HALF_ORDER, "high-S signature produced");
+    }
+}
+
+// (n - 1) / 2 of secp256r1, big endian
+const P256_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xde, 0x73, 0x7d, 0x56, 0xd3, 0x8b, 0xcf, 0x42, 0x79, 0xdc, 0xe5, 0x61, 0x7e, 0x31, 0x92, 0xa8,
+];
+
+fn p256_s(sig: &[u8]) -> [u8; 32] {
+    sig[32..64].try_into().unwrap()
+}
+
+// group orders, big endian
+const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+const P256_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+
+// (r, s) -> (r, n - s), which is still a valid but high-S signature
+fn to_high_s(sig: &[u8], order: &[u8; 32]) -> Vec<u8> {
+    let mut result = sig.to_vec();
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = order[i] as i16 - sig[32 + i] as i16 - borrow;
+        borrow = if diff < 0 { 1 } else { 0 };
+        if diff < 0 {
+            diff += 256;
+        }
+        result[32 + i] = diff as u8;
+    }
+    result
+}
+
+fn replace_sig(op: &Operation, sig: &[u8]) -> Vec<u8> {
+    let mut pairs = decode_map(&op.to_vec().unwrap());
+    set_field(&mut pairs, "sig", text(&URL_SAFE_NO_PAD.encode(sig)));
+    encode(&Value::Map(pairs))
+}
+
+#[test]
+fn test_high_s_signature() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let binary_did = genesis.get_binary_did().unwrap();
+    let sig = genesis.get_signature().unwrap();
+
+    // secp256k1
+    let buf = replace_sig(&genesis, &to_high_s(&sig, &SECP256K1_ORDER));
+    let res = validate_genesis_operation(&buf, &binary_did, 0);
+    assert!(matches!(res, Err(Error::HighSSignature)));
+
+    // secp256r1
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .sign(&rotation_key2)
+        .unwrap();
+    let genesis_buf = genesis.to_vec().unwrap();
+    let sig = update.get_signature().unwrap();
+    let buf = replace_sig(&update, &to_high_s(&sig, &P256_ORDER));
+    let res = validate_2_operations(&genesis_buf, &buf, 1);
+    assert!(matches!(res, Err(Error::HighSSignature)));
+    // the low-S form is still accepted
+    let buf = replace_sig(&update, &sig);
+    assert!(validate_2_operations(&genesis_buf, &buf, 1).is_ok());
+}
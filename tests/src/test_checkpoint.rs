@@ -0,0 +1,121 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{secp256k1_key, secp256r1_key};
+use crate::test_key_index::{build_history, to_cursors};
+use ckb_did_plc_utils::{
+    cid::Cid,
+    error::{Error, HistoryError, HistoryStage},
+    operation::{
+        Operation, validate_operation_history, validate_operation_history_from_checkpoint,
+    },
+};
+
+#[test]
+fn test_validate_from_checkpoint() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+
+    // the full verification returns the head CID
+    let head = validate_operation_history(
+        &binary_did,
+        to_cursors(&history),
+        vec![0, 1, 0, 1],
+        &msg,
+        &final_sig,
+    )
+    .unwrap();
+    assert_eq!(head, Cid::from_dag_cbor(&history[2]));
+
+    // from a checkpoint at update1, only update2 is verified
+    let checkpoint = Cid::from_dag_cbor(&history[1]);
+    let res = validate_operation_history_from_checkpoint(
+        &checkpoint,
+        to_cursors(&history[1..]),
+        vec![0, 1],
+        &msg,
+        &final_sig,
+    );
+    assert_eq!(res.unwrap(), head);
+
+    // the checkpoint operation alone, nothing new since
+    let res = validate_operation_history_from_checkpoint(
+        &head,
+        to_cursors(&history[2..]),
+        vec![1],
+        &msg,
+        &final_sig,
+    );
+    assert_eq!(res.unwrap(), head);
+
+    // the first operation must be the checkpoint
+    let res = validate_operation_history_from_checkpoint(
+        &checkpoint,
+        to_cursors(&history[2..]),
+        vec![1],
+        &msg,
+        &final_sig,
+    );
+    assert!(matches!(
+        res,
+        Err(HistoryError {
+            index: 0,
+            stage: HistoryStage::Checkpoint,
+            error: Error::CheckpointMismatched,
+        })
+    ));
+
+    // wrong key index for the transition after the checkpoint
+    let res = validate_operation_history_from_checkpoint(
+        &checkpoint,
+        to_cursors(&history[1..]),
+        vec![1, 1],
+        &msg,
+        &final_sig,
+    );
+    assert!(matches!(
+        res,
+        Err(HistoryError {
+            index: 1,
+            stage: HistoryStage::Transition,
+            ..
+        })
+    ));
+
+    // final authorization signed by a key which is not a rotation key
+    let res = validate_operation_history_from_checkpoint(
+        &checkpoint,
+        to_cursors(&history[1..]),
+        vec![0, 1],
+        &msg,
+        &secp256k1_key(9).sign(&msg).unwrap(),
+    );
+    assert!(matches!(
+        res,
+        Err(HistoryError {
+            index: 1,
+            stage: HistoryStage::Final,
+            ..
+        })
+    ));
+
+    // the indices have one entry per operation, not one more
+    let res = validate_operation_history_from_checkpoint(
+        &checkpoint,
+        to_cursors(&history[1..]),
+        vec![0, 1, 0],
+        &msg,
+        &final_sig,
+    );
+    assert!(matches!(
+        res,
+        Err(HistoryError {
+            error: Error::InvalidHistory,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,87 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_vectors::set_prev_field;
+use ckb_did_plc_utils::{
+    base32::{self, Alphabet},
+    builder::OperationBuilder,
+    cid::{CID_LENGTH, Cid, DAG_CBOR_CODEC, SHA2_256_CODE},
+    error::Error,
+    operation::validate_2_operations,
+};
+
+#[test]
+fn test_cid_round_trip() {
+    let cid = Cid::from_dag_cbor(b"hello");
+    assert_eq!(cid.version(), 1);
+    assert_eq!(cid.codec(), DAG_CBOR_CODEC);
+    assert_eq!(cid.hash_code(), SHA2_256_CODE);
+
+    let s = cid.to_string();
+    assert!(s.starts_with("bafyrei"));
+    assert_eq!(s.parse::<Cid>().unwrap(), cid);
+
+    let bytes = cid.to_bytes();
+    assert_eq!(bytes.len(), CID_LENGTH);
+    assert_eq!(Cid::from_bytes(&bytes).unwrap(), cid);
+    assert_eq!(&bytes[4..], cid.digest());
+}
+
+#[test]
+fn test_invalid_cid() {
+    let cid = Cid::from_dag_cbor(b"hello");
+    let s = cid.to_string();
+    let bytes = cid.to_bytes();
+
+    let mut raw_codec = bytes.to_vec();
+    raw_codec[1] = 0x55;
+    let raw_codec = format!(
+        "b{}",
+        base32::encode(Alphabet::Rfc4648Lower { padding: false }, &raw_codec)
+    );
+
+    let cases = [
+        ("empty", String::new()),
+        ("base58btc", format!("z{}", &s[1..])),
+        ("uppercase", s.to_uppercase()),
+        ("truncated", s[..s.len() - 2].to_string()),
+        ("not base32", "b!!!!".to_string()),
+        ("raw codec", raw_codec),
+    ];
+    for (label, s) in cases {
+        let res = s.parse::<Cid>();
+        assert!(
+            matches!(res, Err(Error::InvalidCid)),
+            "[{label}] expect InvalidCid -> got: {res:?}"
+        );
+    }
+    assert!(matches!(
+        Cid::from_bytes(&bytes[..35]),
+        Err(Error::InvalidCid)
+    ));
+}
+
+#[test]
+fn test_operation_prev_cid() {
+    let rotation_key1 = secp256k1_key(1);
+    let genesis = genesis_operation(&rotation_key1, &secp256r1_key());
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .sign(&rotation_key1)
+        .unwrap();
+    assert_eq!(
+        update.get_prev().unwrap(),
+        Some(genesis.generate_cid().unwrap())
+    );
+
+    let genesis_buf = genesis.to_vec().unwrap();
+    let update_buf = set_prev_field(&update.to_vec().unwrap(), "bnotacid");
+    let res = validate_2_operations(&genesis_buf, &update_buf, 0);
+    assert!(matches!(res, Err(Error::InvalidCid)));
+
+    // a well-formed CID pointing to some other operation
+    let other = Cid::from_dag_cbor(b"other").to_string();
+    let update_buf = set_prev_field(&update.to_vec().unwrap(), &other);
+    let res = validate_2_operations(&genesis_buf, &update_buf, 0);
+    assert!(matches!(res, Err(Error::InvalidPrev)));
+}
@@ -0,0 +1,112 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{
+    dag_cbor::validate_dag_cbor,
+    error::Error,
+    operation::{Operation, validate_genesis_operation},
+};
+
+fn genesis_buf() -> Vec<u8> {
+    genesis_operation(&secp256k1_key(1), &secp256r1_key())
+        .to_vec()
+        .unwrap()
+}
+
+#[test]
+fn test_dag_cbor_canonical() {
+    let buf = genesis_buf();
+    assert!(validate_dag_cbor(&buf).is_ok());
+    assert!(Operation::from_slice_strict(&buf).is_ok());
+
+    assert!(validate_dag_cbor(&[0x01]).is_ok());
+    assert!(validate_dag_cbor(&[0x18, 0x18]).is_ok());
+    assert!(validate_dag_cbor(&[0xa2, 0x61, 0x62, 0x01, 0x62, 0x61, 0x61, 0x02]).is_ok());
+    // 42(h'0001'), a CID
+    assert!(validate_dag_cbor(&[0xd8, 0x2a, 0x42, 0x00, 0x01]).is_ok());
+}
+
+#[test]
+fn test_dag_cbor_trailing_bytes() {
+    let mut buf = genesis_buf();
+    let binary_did = Operation::from_slice(&buf)
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    buf.push(0x00);
+    assert!(Operation::from_slice(&buf).is_ok());
+    assert!(matches!(
+        Operation::from_slice_strict(&buf),
+        Err(Error::NonCanonicalCbor)
+    ));
+    assert!(matches!(
+        validate_genesis_operation(&buf, &binary_did, 0),
+        Err(Error::NonCanonicalCbor)
+    ));
+}
+
+#[test]
+fn test_dag_cbor_non_canonical() {
+    let cases: &[(&str, &[u8])] = &[
+        // {"b": 1, "a": 2}
+        ("unsorted keys", &[0xa2, 0x61, 0x62, 0x01, 0x61, 0x61, 0x02]),
+        // {"aa": 1, "b": 2}, shorter keys come first
+        (
+            "longer key first",
+            &[0xa2, 0x62, 0x61, 0x61, 0x01, 0x61, 0x62, 0x02],
+        ),
+        // {"a": 1, "a": 2}
+        (
+            "duplicated keys",
+            &[0xa2, 0x61, 0x61, 0x01, 0x61, 0x61, 0x02],
+        ),
+        // {1: 1}
+        ("integer key", &[0xa1, 0x01, 0x01]),
+        // 1 encoded in 1 extra byte
+        ("non-minimal integer", &[0x18, 0x01]),
+        // 1 encoded in 2 extra bytes
+        ("non-minimal integer u16", &[0x19, 0x00, 0x01]),
+        // "a" with its length encoded in 1 extra byte
+        ("non-minimal length", &[0x78, 0x01, 0x61]),
+        // indefinite-length map {"a": 1}
+        ("indefinite map", &[0xbf, 0x61, 0x61, 0x01, 0xff]),
+        // indefinite-length text "a"
+        ("indefinite text", &[0x7f, 0x61, 0x61, 0xff]),
+        // 1.5 as half float
+        ("float", &[0xf9, 0x3e, 0x00]),
+        ("undefined", &[0xf7]),
+        // tag 1 (epoch time)
+        ("tag", &[0xc1, 0x01]),
+        // tag 42 takes bytes starting with 0x00
+        ("tag 42 integer", &[0xd8, 0x2a, 0x01]),
+        ("tag 42 text", &[0xd8, 0x2a, 0x61, 0x78]),
+        ("tag 42 without prefix", &[0xd8, 0x2a, 0x41, 0x01]),
+        ("tag 42 empty", &[0xd8, 0x2a, 0x40]),
+        ("trailing bytes", &[0x01, 0x02]),
+    ];
+    for (label, buf) in cases {
+        let res = validate_dag_cbor(buf);
+        assert!(
+            matches!(res, Err(Error::NonCanonicalCbor)),
+            "[{label}] expect NonCanonicalCbor -> got: {res:?}"
+        );
+    }
+}
+
+#[test]
+fn test_dag_cbor_malformed() {
+    let cases: &[(&str, &[u8])] = &[
+        ("empty", &[]),
+        ("truncated array", &[0x82, 0x01]),
+        ("truncated text", &[0x63, 0x61]),
+        ("invalid utf-8", &[0x61, 0xff]),
+        ("reserved additional info", &[0x1c]),
+    ];
+    for (label, buf) in cases {
+        let res = validate_dag_cbor(buf);
+        assert!(
+            matches!(res, Err(Error::InvalidCbor)),
+            "[{label}] expect InvalidCbor -> got: {res:?}"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{
+    did::{PlcDid, Web5Did},
+    error::Error,
+    operation::parse_local_id,
+};
+
+#[test]
+fn test_plc_did() {
+    let genesis = genesis_operation(&secp256k1_key(1), &secp256r1_key());
+    let did = genesis.get_plc_did().unwrap();
+    assert_eq!(did.as_bytes().to_vec(), genesis.get_binary_did().unwrap());
+    assert_eq!(did.to_string(), genesis.get_did().unwrap());
+
+    // round trip
+    let s = did.to_string();
+    assert_eq!(s.len(), "did:plc:".len() + 24);
+    assert_eq!(s.parse::<PlcDid>().unwrap(), did);
+    assert_eq!(PlcDid::from_bytes(did.as_bytes()).unwrap(), did);
+    assert_eq!(
+        parse_local_id(s.as_bytes()).unwrap(),
+        did.as_bytes().to_vec()
+    );
+
+    let invalid = [
+        // wrong method
+        s.replace("did:plc:", "did:web:"),
+        // too short, too long
+        s[..s.len() - 1].to_string(),
+        format!("{s}a"),
+        // uppercase
+        format!("did:plc:{}", s["did:plc:".len()..].to_uppercase()),
+        // not in the base32 alphabet
+        format!("{}1", &s[..s.len() - 1]),
+        format!("{}=", &s[..s.len() - 1]),
+    ];
+    for s in invalid {
+        assert!(matches!(s.parse::<PlcDid>(), Err(Error::InvalidDidFormat)));
+        assert!(matches!(
+            parse_local_id(s.as_bytes()),
+            Err(Error::InvalidDidFormat)
+        ));
+    }
+    assert!(matches!(
+        PlcDid::from_bytes(&[0u8; 16]),
+        Err(Error::InvalidDidFormat)
+    ));
+}
+
+#[test]
+fn test_web5_did() {
+    let type_id: [u8; 20] = core::array::from_fn(|i| i as u8);
+    let did = Web5Did::new(type_id);
+    let s = did.to_string();
+    assert_eq!(s, "did:web5:aaaqeayeaudaocajbifqydiob4ibceqt");
+    assert_eq!(s.parse::<Web5Did>().unwrap(), did);
+
+    // the type ID is the first 20 bytes of the args
+    let mut args = type_id.to_vec();
+    args.extend_from_slice(&[0xff; 8]);
+    assert_eq!(Web5Did::from_type_id_args(&args).unwrap(), did);
+    assert!(matches!(
+        Web5Did::from_type_id_args(&args[..19]),
+        Err(Error::InvalidDidFormat)
+    ));
+    assert!(matches!(
+        "did:plc:aaaqeayeaudaocajbifqydiob4ibceqt".parse::<Web5Did>(),
+        Err(Error::InvalidDidFormat)
+    ));
+}
@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_legacy::legacy_genesis_operation;
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    diff::{Change, changes_to_json, diff_operations},
+    error::Error,
+    operation::{Operation, Service},
+};
+
+#[test]
+fn test_diff_operations() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    assert_eq!(diff_operations(&genesis, &genesis).unwrap(), vec![]);
+
+    let new_signing_key = secp256k1_key(5);
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .rotation_keys(vec![
+            rotation_key2.did_key(),
+            rotation_key1.did_key(),
+            secp256k1_key(3).did_key(),
+        ])
+        .verification_method("atproto", &new_signing_key.did_key())
+        .also_known_as(vec!["at://bob.example.com".to_string()])
+        .service(
+            "atproto_pds",
+            "AtprotoPersonalDataServer",
+            "https://pds.example.com",
+        )
+        .sign(&rotation_key1)
+        .unwrap();
+    let changes = diff_operations(&genesis, &update).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            Change::RotationKeyAdded(secp256k1_key(3).did_key()),
+            Change::RotationKeysReordered {
+                from: vec![rotation_key1.did_key(), rotation_key2.did_key()],
+                to: vec![
+                    rotation_key2.did_key(),
+                    rotation_key1.did_key(),
+                    secp256k1_key(3).did_key(),
+                ],
+            },
+            Change::VerificationMethodChanged {
+                id: "atproto".to_string(),
+                from: secp256k1_key(0).did_key(),
+                to: new_signing_key.did_key(),
+            },
+            Change::AlsoKnownAsRemoved("at://alice.example.com".to_string()),
+            Change::AlsoKnownAsAdded("at://bob.example.com".to_string()),
+            Change::ServiceChanged {
+                id: "atproto_pds".to_string(),
+                from: Service {
+                    r#type: "AtprotoPersonalDataServer".to_string(),
+                    endpoint: "https://example.com".to_string(),
+                },
+                to: Service {
+                    r#type: "AtprotoPersonalDataServer".to_string(),
+                    endpoint: "https://pds.example.com".to_string(),
+                },
+            },
+        ]
+    );
+
+    let json = changes_to_json(&changes[4..]);
+    assert_eq!(
+        json,
+        r#"[{"change":"alsoKnownAsAdded","value":"at://bob.example.com"},{"change":"serviceChanged","id":"atproto_pds","from":{"type":"AtprotoPersonalDataServer","endpoint":"https://example.com"},"to":{"type":"AtprotoPersonalDataServer","endpoint":"https://pds.example.com"}}]"#
+    );
+
+    let tombstone = OperationBuilder::tombstone(&update)
+        .unwrap()
+        .sign(&rotation_key2)
+        .unwrap();
+    assert_eq!(
+        diff_operations(&update, &tombstone).unwrap(),
+        vec![Change::Tombstoned]
+    );
+    assert_eq!(Change::Tombstoned.to_json(), r#"{"change":"tombstoned"}"#);
+    assert!(matches!(
+        diff_operations(&tombstone, &update),
+        Err(Error::DidTombstoned)
+    ));
+}
+
+#[test]
+fn test_diff_legacy_operation() {
+    let signing_key = secp256k1_key(0);
+    let recovery_key = secp256r1_key();
+    let buf = legacy_genesis_operation(&signing_key, &recovery_key, "alice.test", "example.com");
+    let legacy = Operation::from_slice(&buf).unwrap();
+    // the handle and the service are compared as `alsoKnownAs` and `services`
+    let update = OperationBuilder::update(&legacy)
+        .unwrap()
+        .also_known_as(vec!["at://bob.test".to_string()])
+        .sign(&recovery_key)
+        .unwrap();
+    assert_eq!(
+        diff_operations(&legacy, &update).unwrap(),
+        vec![
+            Change::AlsoKnownAsRemoved("at://alice.test".to_string()),
+            Change::AlsoKnownAsAdded("at://bob.test".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    document::{DidDocument, DocumentService, VerificationMethod},
+    error::Error,
+};
+
+#[test]
+fn test_did_document() {
+    let rotation_key1 = secp256k1_key(1);
+    let genesis = genesis_operation(&rotation_key1, &secp256r1_key());
+    let did = genesis.get_did().unwrap();
+    let signing_key = secp256k1_key(0).did_key();
+
+    let doc = DidDocument::from_operation(&did, &genesis).unwrap();
+    assert_eq!(
+        doc.context,
+        vec![
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/multikey/v1",
+            "https://w3id.org/security/suites/secp256k1-2019/v1",
+        ]
+    );
+    assert_eq!(doc.id, did);
+    assert_eq!(doc.also_known_as, vec!["at://alice.example.com"]);
+    assert_eq!(
+        doc.verification_method,
+        vec![VerificationMethod {
+            id: format!("{did}#atproto"),
+            r#type: "Multikey".to_string(),
+            controller: did.clone(),
+            public_key_multibase: signing_key[8..].to_string(),
+        }]
+    );
+    assert_eq!(
+        doc.service,
+        vec![DocumentService {
+            id: "#atproto_pds".to_string(),
+            r#type: "AtprotoPersonalDataServer".to_string(),
+            service_endpoint: "https://example.com".to_string(),
+        }]
+    );
+
+    let expected = format!(
+        concat!(
+            r#"{{"@context":["https://www.w3.org/ns/did/v1","https://w3id.org/security/multikey/v1","#,
+            r#""https://w3id.org/security/suites/secp256k1-2019/v1"],"id":"{did}","#,
+            r#""alsoKnownAs":["at://alice.example.com"],"verificationMethod":[{{"id":"{did}#atproto","#,
+            r#""type":"Multikey","controller":"{did}","publicKeyMultibase":"{key}"}}],"#,
+            r##""service":[{{"id":"#atproto_pds","type":"AtprotoPersonalDataServer","##,
+            r#""serviceEndpoint":"https://example.com"}}]}}"#
+        ),
+        did = did,
+        key = &signing_key[8..]
+    );
+    assert_eq!(doc.to_json(), expected);
+}
+
+#[test]
+fn test_did_document_from_history() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .verification_method("atproto", &rotation_key2.did_key())
+        .also_known_as(vec!["at://a\"b.example.com".to_string()])
+        .service(
+            "atproto_labeler",
+            "AtprotoLabeler",
+            "https://labeler.example.com",
+        )
+        .sign(&rotation_key1)
+        .unwrap();
+    let history = vec![genesis.to_vec().unwrap(), update.to_vec().unwrap()];
+
+    let doc = DidDocument::from_history(&history).unwrap();
+    assert_eq!(doc.id, genesis.get_did().unwrap());
+    assert_eq!(
+        doc.context[2],
+        "https://w3id.org/security/suites/ecdsa-2019/v1"
+    );
+    // services in the order of the DAG-CBOR map
+    let ids: Vec<&str> = doc.service.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["#atproto_pds", "#atproto_labeler"]);
+    assert!(
+        doc.to_json()
+            .contains(r#""alsoKnownAs":["at://a\"b.example.com"]"#)
+    );
+
+    let tombstone = OperationBuilder::tombstone(&update)
+        .unwrap()
+        .sign(&rotation_key1)
+        .unwrap();
+    let history = vec![
+        genesis.to_vec().unwrap(),
+        update.to_vec().unwrap(),
+        tombstone.to_vec().unwrap(),
+    ];
+    assert!(matches!(
+        DidDocument::from_history(&history),
+        Err(Error::DidTombstoned)
+    ));
+    assert!(matches!(
+        DidDocument::from_history::<Vec<u8>>(&[]),
+        Err(Error::InvalidHistory)
+    ));
+}
@@ -0,0 +1,292 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::common::{decode_map, set_field, sign_map, text};
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_legacy::legacy_genesis_operation;
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    cid::Cid,
+    error::Error,
+    fork::{LogEntry, RECOVERY_WINDOW, ResolvedLog, parse_created_at, resolve_audit_log},
+    operation::Operation,
+    signer::PrivateKey,
+};
+
+const HOUR: u64 = 60 * 60 * 1000;
+const START: u64 = 1_700_000_000_000;
+
+fn entry(op: &Operation, created_at: u64) -> LogEntry {
+    LogEntry {
+        cid: op.generate_cid().unwrap(),
+        operation: op.to_vec().unwrap(),
+        created_at,
+    }
+}
+
+fn update(prev: &Operation, handle: &str, key: &PrivateKey) -> Operation {
+    OperationBuilder::update(prev)
+        .unwrap()
+        .also_known_as(vec![format!("at://{handle}")])
+        .sign(key)
+        .unwrap()
+}
+
+#[test]
+fn test_linear_audit_log() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update1 = update(&genesis, "bob.example.com", &rotation_key2);
+    let update2 = update(&update1, "carol.example.com", &rotation_key1);
+    let binary_did = genesis.get_binary_did().unwrap();
+    let log = vec![
+        entry(&genesis, START),
+        entry(&update1, START + HOUR),
+        entry(&update2, START + 2 * HOUR),
+    ];
+    assert_eq!(
+        resolve_audit_log(&binary_did, &log).unwrap(),
+        ResolvedLog {
+            canonical: vec![0, 1, 2],
+            nullified: vec![],
+        }
+    );
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &[]),
+        Err(Error::InvalidHistory)
+    ));
+}
+
+#[test]
+fn test_nullify_within_recovery_window() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    // signed by the lower priority key
+    let update1 = update(&genesis, "bob.example.com", &rotation_key2);
+    let update2 = update(&update1, "carol.example.com", &rotation_key2);
+    // the higher priority key rewrites history after genesis
+    let recovery = update(&genesis, "alice.example.com", &rotation_key1);
+    let binary_did = genesis.get_binary_did().unwrap();
+
+    let log = vec![
+        entry(&genesis, START),
+        entry(&update1, START + HOUR),
+        entry(&update2, START + 2 * HOUR),
+        entry(&recovery, START + HOUR + RECOVERY_WINDOW),
+    ];
+    assert_eq!(
+        resolve_audit_log(&binary_did, &log).unwrap(),
+        ResolvedLog {
+            canonical: vec![0, 3],
+            nullified: vec![1, 2],
+        }
+    );
+
+    // the window starts from the first nullified operation
+    let mut late_log = log.clone();
+    late_log[3].created_at = START + HOUR + RECOVERY_WINDOW + 1;
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &late_log),
+        Err(Error::LateRecovery)
+    ));
+}
+
+#[test]
+fn test_nullify_requires_higher_priority_key() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let binary_did = genesis.get_binary_did().unwrap();
+
+    // same priority
+    let update1 = update(&genesis, "bob.example.com", &rotation_key2);
+    let fork = update(&genesis, "carol.example.com", &rotation_key2);
+    let log = vec![
+        entry(&genesis, START),
+        entry(&update1, START + HOUR),
+        entry(&fork, START + 2 * HOUR),
+    ];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::VerifySignatureFailed)
+    ));
+
+    // lower priority
+    let update1 = update(&genesis, "bob.example.com", &rotation_key1);
+    let fork = update(&genesis, "carol.example.com", &rotation_key2);
+    let log = vec![
+        entry(&genesis, START),
+        entry(&update1, START + HOUR),
+        entry(&fork, START + 2 * HOUR),
+    ];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::VerifySignatureFailed)
+    ));
+}
+
+#[test]
+fn test_nullify_legacy_genesis() {
+    let signing_key = secp256k1_key(0);
+    let recovery_key = secp256r1_key();
+    let genesis = Operation::from_slice(&legacy_genesis_operation(
+        &signing_key,
+        &recovery_key,
+        "alice.test",
+        "https://pds.test",
+    ))
+    .unwrap();
+    let binary_did = genesis.get_binary_did().unwrap();
+
+    // once normalized, recoveryKey has higher priority than signingKey
+    let update1 = update(&genesis, "bob.example.com", &signing_key);
+    let recovery = update(&genesis, "carol.example.com", &recovery_key);
+    let log = vec![
+        entry(&genesis, START),
+        entry(&update1, START + HOUR),
+        entry(&recovery, START + 2 * HOUR),
+    ];
+    assert_eq!(
+        resolve_audit_log(&binary_did, &log).unwrap(),
+        ResolvedLog {
+            canonical: vec![0, 2],
+            nullified: vec![1],
+        }
+    );
+
+    let log = vec![
+        entry(&genesis, START),
+        entry(&recovery, START + HOUR),
+        entry(&update1, START + 2 * HOUR),
+    ];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::VerifySignatureFailed)
+    ));
+}
+
+#[test]
+fn test_nullified_operation_not_usable_as_prev() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update1 = update(&genesis, "bob.example.com", &rotation_key2);
+    let recovery = update(&genesis, "alice.example.com", &rotation_key1);
+    let on_nullified = update(&update1, "carol.example.com", &rotation_key1);
+    let binary_did = genesis.get_binary_did().unwrap();
+
+    let log = vec![
+        entry(&genesis, START),
+        entry(&update1, START + HOUR),
+        entry(&recovery, START + 2 * HOUR),
+        entry(&on_nullified, START + 3 * HOUR),
+    ];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::MisorderedOperation)
+    ));
+}
+
+#[test]
+fn test_invalid_audit_log() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update1 = update(&genesis, "bob.example.com", &rotation_key2);
+    let binary_did = genesis.get_binary_did().unwrap();
+
+    // not in order of creation
+    let log = vec![entry(&genesis, START + HOUR), entry(&update1, START)];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::MisorderedOperation)
+    ));
+
+    // CID doesn't match the operation
+    let mut log = vec![entry(&genesis, START), entry(&update1, START + HOUR)];
+    log[1].cid = log[0].cid;
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::InvalidCid)
+    ));
+
+    // starts with a non-genesis operation
+    let log = vec![entry(&update1, START)];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::NotGenesisOperation)
+    ));
+
+    // after a tombstone
+    let tombstone = OperationBuilder::tombstone(&genesis)
+        .unwrap()
+        .sign(&rotation_key1)
+        .unwrap();
+    let mut pairs = decode_map(
+        &OperationBuilder::update(&genesis)
+            .unwrap()
+            .build_unsigned()
+            .unwrap()
+            .to_vec()
+            .unwrap(),
+    );
+    let tombstone_cid = tombstone.generate_cid().unwrap();
+    set_field(&mut pairs, "prev", text(&tombstone_cid.to_string()));
+    let after_tombstone = sign_map(pairs, &rotation_key1);
+    let log = vec![
+        entry(&genesis, START),
+        entry(&tombstone, START + HOUR),
+        LogEntry {
+            cid: Cid::from_dag_cbor(&after_tombstone),
+            operation: after_tombstone,
+            created_at: START + 2 * HOUR,
+        },
+    ];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::MisorderedOperation)
+    ));
+
+    // a second genesis operation
+    let log = vec![entry(&genesis, START), entry(&genesis, START + HOUR)];
+    assert!(matches!(
+        resolve_audit_log(&binary_did, &log),
+        Err(Error::MisorderedOperation)
+    ));
+}
+
+#[test]
+fn test_parse_created_at() {
+    assert_eq!(parse_created_at("1970-01-01T00:00:00Z").unwrap(), 0);
+    assert_eq!(parse_created_at("1970-01-01T00:00:00.001Z").unwrap(), 1);
+    assert_eq!(
+        parse_created_at("2023-04-28T17:40:21.519Z").unwrap(),
+        1_682_703_621_519
+    );
+    assert_eq!(
+        parse_created_at("2024-02-29T00:00:00.5Z").unwrap(),
+        1_709_164_800_500
+    );
+    assert_eq!(
+        parse_created_at("2024-02-29T00:00:00.123456Z").unwrap(),
+        1_709_164_800_123
+    );
+    for s in [
+        "",
+        "2023-04-28T17:40:21.519",
+        "2023-04-28 17:40:21.519Z",
+        "2023-04-28T17:40:21.519+00:00",
+        "2023-02-29T00:00:00Z",
+        "2023-13-01T00:00:00Z",
+        "2023-04-28T24:00:00Z",
+        "2023-04-28T17:40:21.Z",
+        "2023-04-28T17:40:21.5a9Z",
+        "1969-12-31T23:59:59Z",
+    ] {
+        assert!(
+            matches!(parse_created_at(s), Err(Error::InvalidTimestamp)),
+            "{s}"
+        );
+    }
+}
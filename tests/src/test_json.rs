@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_legacy::legacy_genesis_operation;
+use ckb_did_plc_utils::{
+    base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD},
+    builder::OperationBuilder,
+    error::Error,
+    fork::{parse_audit_log, resolve_audit_log},
+    operation::{Operation, validate_2_operations, validate_genesis_operation},
+};
+
+#[test]
+fn test_operation_from_json() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let op = genesis_operation(&rotation_key1, &rotation_key2);
+    let buf = op.to_vec().unwrap();
+    let sig = URL_SAFE_NO_PAD.encode(op.get_signature().unwrap());
+
+    // keys in the order of the PLC directory, not DAG-CBOR
+    let json = format!(
+        r#"{{
+            "sig": "{sig}",
+            "prev": null,
+            "type": "plc_operation",
+            "services": {{
+                "atproto_pds": {{
+                    "type": "AtprotoPersonalDataServer",
+                    "endpoint": "https://example.com"
+                }}
+            }},
+            "alsoKnownAs": ["at://alice.example.com"],
+            "rotationKeys": ["{}", "{}"],
+            "verificationMethods": {{ "atproto": "{}" }}
+        }}"#,
+        rotation_key1.did_key(),
+        rotation_key2.did_key(),
+        secp256k1_key(0).did_key(),
+    );
+    let parsed = Operation::from_json(&json).unwrap();
+    assert_eq!(parsed.to_vec().unwrap(), buf);
+    let binary_did = op.get_binary_did().unwrap();
+    assert!(validate_genesis_operation(&parsed.to_vec().unwrap(), &binary_did, 0).is_ok());
+}
+
+#[test]
+fn test_operation_json_round_trip() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let tombstone = OperationBuilder::tombstone(&genesis)
+        .unwrap()
+        .sign(&rotation_key2)
+        .unwrap();
+    let legacy = Operation::from_slice(&legacy_genesis_operation(
+        &rotation_key1,
+        &rotation_key2,
+        "alice.test",
+        "https://example.com",
+    ))
+    .unwrap();
+    for op in [genesis, tombstone, legacy] {
+        let json = op.to_json().unwrap();
+        let parsed = Operation::from_json(&json).unwrap();
+        assert_eq!(parsed.to_vec().unwrap(), op.to_vec().unwrap());
+        assert_eq!(parsed.to_json().unwrap(), json);
+    }
+}
+
+#[test]
+fn test_json_escapes() {
+    let op = Operation::from_json(r#"{"b": "é😀\n\"\/", "a": [1, -2, true, null]}"#).unwrap();
+    assert_eq!(
+        op.to_json().unwrap(),
+        r#"{"a":[1,-2,true,null],"b":"é😀\n\"/"}"#
+    );
+}
+
+#[test]
+fn test_invalid_json() {
+    let cases = [
+        "",
+        "[]",
+        "{",
+        r#"{"a": 1,}"#,
+        r#"{"a": 1} 2"#,
+        r#"{"a": 1, "a": 2}"#,
+        r#"{"a": 1.5}"#,
+        r#"{"a": 1e3}"#,
+        r#"{"a": 01}"#,
+        r#"{"a": "\x"}"#,
+        r#"{"a": "\ud83d"}"#,
+        r#"{"a": "\u+0ab"}"#,
+        "{\"a\": \"\n\"}",
+        r#"{"a": nul}"#,
+    ];
+    for json in cases {
+        assert!(
+            matches!(
+                Operation::from_json(json),
+                Err(Error::InvalidJson | Error::InvalidOperation)
+            ),
+            "{json}"
+        );
+    }
+}
+
+#[test]
+fn test_parse_audit_log() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .also_known_as(vec!["at://bob.example.com".to_string()])
+        .sign(&rotation_key2)
+        .unwrap();
+    let did = genesis.get_did().unwrap();
+    let entry = |op: &Operation, created_at: &str| {
+        format!(
+            r#"{{"did":"{did}","operation":{},"cid":"{}","nullified":false,"createdAt":"{created_at}"}}"#,
+            op.to_json().unwrap(),
+            op.generate_cid().unwrap(),
+        )
+    };
+    let json = format!(
+        "[{},{}]",
+        entry(&genesis, "2024-01-01T00:00:00.000Z"),
+        entry(&update, "2024-01-02T00:00:00.000Z"),
+    );
+    let log = parse_audit_log(&json).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].operation, genesis.to_vec().unwrap());
+    assert_eq!(log[1].operation, update.to_vec().unwrap());
+    assert_eq!(log[1].created_at - log[0].created_at, 24 * 60 * 60 * 1000);
+    assert!(validate_2_operations(&log[0].operation, &log[1].operation, 1).is_ok());
+    let resolved = resolve_audit_log(&genesis.get_binary_did().unwrap(), &log).unwrap();
+    assert_eq!(resolved.canonical, vec![0, 1]);
+
+    // CID of another operation
+    let json = json.replacen(
+        &update.generate_cid().unwrap().to_string(),
+        &genesis.generate_cid().unwrap().to_string(),
+        1,
+    );
+    assert!(matches!(parse_audit_log(&json), Err(Error::InvalidCid)));
+}
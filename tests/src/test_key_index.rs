@@ -0,0 +1,94 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    error::Error,
+    operation::{
+        find_rotation_key_indices, validate_2_operations_any_key,
+        validate_genesis_operation_any_key, validate_operation_history,
+    },
+};
+use molecule::lazy_reader::Cursor;
+
+/// genesis (signed by key 0), update (signed by key 1), update (signed by key 0)
+pub fn build_history() -> Vec<Vec<u8>> {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update1 = OperationBuilder::update(&genesis)
+        .unwrap()
+        .also_known_as(vec!["at://bob.example.com".to_string()])
+        .sign(&rotation_key2)
+        .unwrap();
+    let update2 = OperationBuilder::update(&update1)
+        .unwrap()
+        .service(
+            "atproto_pds",
+            "AtprotoPersonalDataServer",
+            "https://pds.example.com",
+        )
+        .sign(&rotation_key1)
+        .unwrap();
+    vec![
+        genesis.to_vec().unwrap(),
+        update1.to_vec().unwrap(),
+        update2.to_vec().unwrap(),
+    ]
+}
+
+pub fn to_cursors(history: &[Vec<u8>]) -> Vec<Cursor> {
+    history.iter().map(|op| Cursor::from(op.clone())).collect()
+}
+
+#[test]
+fn test_find_rotation_key_index() {
+    let history = build_history();
+    let binary_did = ckb_did_plc_utils::operation::Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    assert_eq!(
+        validate_genesis_operation_any_key(&history[0], &binary_did).unwrap(),
+        0
+    );
+    assert_eq!(
+        validate_2_operations_any_key(&history[0], &history[1]).unwrap(),
+        1
+    );
+    assert_eq!(
+        validate_2_operations_any_key(&history[1], &history[2]).unwrap(),
+        0
+    );
+    // no rotation key signed it
+    assert!(matches!(
+        validate_2_operations_any_key(&history[1], &history[1]),
+        Err(Error::InvalidPrev)
+    ));
+}
+
+#[test]
+fn test_find_rotation_key_indices() {
+    let history = build_history();
+    let binary_did = ckb_did_plc_utils::operation::Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+
+    let indices = find_rotation_key_indices(&binary_did, &history, &msg, &final_sig).unwrap();
+    assert_eq!(indices, vec![0, 1, 0, 1]);
+    assert!(
+        validate_operation_history(&binary_did, to_cursors(&history), indices, &msg, &final_sig)
+            .is_ok()
+    );
+
+    // signed by a key which is not a rotation key
+    let final_sig = secp256k1_key(9).sign(&msg).unwrap();
+    let res = find_rotation_key_indices(&binary_did, &history, &msg, &final_sig);
+    assert!(matches!(res, Err(Error::VerifySignatureFailed)));
+
+    let res = find_rotation_key_indices::<Vec<u8>>(&binary_did, &[], &msg, &final_sig);
+    assert!(matches!(res, Err(Error::InvalidHistory)));
+}
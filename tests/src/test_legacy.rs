@@ -0,0 +1,170 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::common::{sign_map, text};
+use crate::test_builder::{secp256k1_key, secp256r1_key};
+use crate::test_key_index::to_cursors;
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    cbor4ii::core::Value,
+    document::DidDocument,
+    error::{Error, HistoryError, HistoryStage},
+    operation::{
+        Operation, OperationType, find_rotation_key_indices, validate_2_operations,
+        validate_genesis_operation, validate_operation_history,
+    },
+    signer::PrivateKey,
+};
+
+/// A legacy `create` operation signed by `signing_key`, keys in DAG-CBOR order
+pub fn legacy_genesis_operation(
+    signing_key: &PrivateKey,
+    recovery_key: &PrivateKey,
+    handle: &str,
+    service: &str,
+) -> Vec<u8> {
+    let pairs = vec![
+        (text("prev"), Value::Null),
+        (text("type"), text("create")),
+        (text("handle"), text(handle)),
+        (text("service"), text(service)),
+        (text("signingKey"), text(&signing_key.did_key())),
+        (text("recoveryKey"), text(&recovery_key.did_key())),
+    ];
+    sign_map(pairs, signing_key)
+}
+
+#[test]
+fn test_legacy_operation() {
+    let signing_key = secp256k1_key(0);
+    let recovery_key = secp256r1_key();
+    let buf = legacy_genesis_operation(&signing_key, &recovery_key, "alice.test", "example.com");
+    let op = Operation::from_slice_strict(&buf).unwrap();
+    assert_eq!(op.get_type().unwrap(), OperationType::Create);
+    let binary_did = op.get_binary_did().unwrap();
+    // legacy rotation keys are in map order: signingKey, recoveryKey
+    assert!(validate_genesis_operation(&buf, &binary_did, 0).is_ok());
+}
+
+#[test]
+fn test_normalize_legacy_operation() {
+    let signing_key = secp256k1_key(0);
+    let recovery_key = secp256r1_key();
+    let buf = legacy_genesis_operation(
+        &signing_key,
+        &recovery_key,
+        "https://alice.test",
+        "example.com",
+    );
+    let op = Operation::from_slice(&buf).unwrap();
+    let normalized = op.normalize().unwrap();
+    assert_eq!(normalized.get_type().unwrap(), OperationType::PlcOperation);
+    assert!(normalized.validate().is_ok());
+    assert_eq!(normalized.get_prev().unwrap(), None);
+    assert_eq!(
+        normalized.get_signature().unwrap(),
+        op.get_signature().unwrap()
+    );
+    let rotation_keys: Vec<String> = normalized
+        .get_rotation_keys()
+        .unwrap()
+        .iter()
+        .map(|k| k.to_did_key())
+        .collect();
+    assert_eq!(
+        rotation_keys,
+        vec![recovery_key.did_key(), signing_key.did_key()]
+    );
+    assert_eq!(
+        normalized.get_verification_methods().unwrap()["atproto"],
+        signing_key.did_key()
+    );
+    assert_eq!(
+        normalized.get_also_known_as().unwrap(),
+        vec!["at://alice.test"]
+    );
+    let services = normalized.get_services().unwrap();
+    assert_eq!(services["atproto_pds"].r#type, "AtprotoPersonalDataServer");
+    assert_eq!(services["atproto_pds"].endpoint, "https://example.com");
+    // canonical form
+    assert!(Operation::from_slice_strict(&normalized.to_vec().unwrap()).is_ok());
+
+    // non-legacy operations are returned as is
+    assert!(normalized.normalize().unwrap() == normalized);
+
+    let doc = DidDocument::from_operation(&op.get_did().unwrap(), &op).unwrap();
+    assert_eq!(doc.also_known_as, vec!["at://alice.test"]);
+    assert_eq!(doc.service[0].service_endpoint, "https://example.com");
+}
+
+#[test]
+fn test_update_legacy_operation() {
+    let signing_key = secp256k1_key(0);
+    let recovery_key = secp256r1_key();
+    let buf = legacy_genesis_operation(
+        &signing_key,
+        &recovery_key,
+        "alice.test",
+        "https://pds.test",
+    );
+    let op = Operation::from_slice(&buf).unwrap();
+    let update = OperationBuilder::update(&op)
+        .unwrap()
+        .sign(&recovery_key)
+        .unwrap();
+    assert_eq!(update.get_also_known_as().unwrap(), vec!["at://alice.test"]);
+    assert!(validate_2_operations(&buf, &update.to_vec().unwrap(), 1).is_ok());
+}
+
+#[test]
+fn test_legacy_final_authorization() {
+    let signing_key = secp256k1_key(0);
+    let recovery_key = secp256r1_key();
+    let buf = legacy_genesis_operation(
+        &signing_key,
+        &recovery_key,
+        "alice.test",
+        "https://pds.test",
+    );
+    let binary_did = Operation::from_slice(&buf)
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let history = vec![buf];
+    let msg = [0x42u8; 32];
+    let validate = |indices: Vec<usize>, final_sig: &[u8]| {
+        validate_operation_history(&binary_did, to_cursors(&history), indices, &msg, final_sig)
+    };
+
+    // the rotation keys of a legacy operation are signingKey and recoveryKey
+    let signing_sig = signing_key.sign(&msg).unwrap();
+    let recovery_sig = recovery_key.sign(&msg).unwrap();
+    assert!(validate(vec![0, 0], &signing_sig).is_ok());
+    assert!(validate(vec![0, 1], &recovery_sig).is_ok());
+    assert_eq!(
+        find_rotation_key_indices(&binary_did, &history, &msg, &recovery_sig).unwrap(),
+        vec![0, 1]
+    );
+
+    let cases = [
+        // out of range
+        (vec![0, 2], signing_sig.clone(), "InvalidKeyIndex"),
+        (vec![0, usize::MAX], signing_sig.clone(), "InvalidKeyIndex"),
+        // signed by the other key
+        (vec![0, 0], recovery_sig.clone(), "VerifySignatureFailed"),
+        // malformed
+        (vec![0, 0], vec![0u8; 63], "InvalidSignature"),
+        (vec![0, 1], vec![], "InvalidSignature"),
+    ];
+    for (indices, final_sig, expected) in cases {
+        let error = validate(indices, &final_sig).unwrap_err();
+        assert!(matches!(
+            error,
+            HistoryError {
+                index: 0,
+                stage: HistoryStage::Final,
+                ..
+            }
+        ));
+        assert_eq!(format!("{:?}", error.error), expected);
+    }
+}
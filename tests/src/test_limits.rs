@@ -0,0 +1,203 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_key_index::{build_history, to_cursors};
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    cid::Cid,
+    error::{Error, HistoryError, HistoryStage},
+    limits::Limits,
+    operation::{
+        Operation, find_rotation_key_indices, validate_operation_history,
+        validate_operation_history_from_checkpoint_with_limits,
+        validate_operation_history_with_limits,
+    },
+};
+
+#[test]
+fn test_history_limits() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+    let validate = |history: &[Vec<u8>], limits: Limits| {
+        validate_operation_history_with_limits(
+            &binary_did,
+            to_cursors(history),
+            vec![0, 1, 0, 1],
+            &msg,
+            &final_sig,
+            &limits,
+        )
+    };
+
+    assert!(validate(&history, Limits::default()).is_ok());
+    assert!(validate(&history, Limits::UNLIMITED).is_ok());
+
+    let cases = [
+        (
+            Limits {
+                max_history_length: 2,
+                ..Limits::default()
+            },
+            "HistoryTooLong",
+        ),
+        (
+            Limits {
+                max_operation_size: history[2].len() - 1,
+                ..Limits::default()
+            },
+            "OperationTooLarge",
+        ),
+        (
+            Limits {
+                max_rotation_keys: 1,
+                ..Limits::default()
+            },
+            "TooManyRotationKeys",
+        ),
+        (
+            Limits {
+                max_services: 0,
+                ..Limits::default()
+            },
+            "TooManyServices",
+        ),
+        (
+            Limits {
+                max_also_known_as: 0,
+                ..Limits::default()
+            },
+            "TooManyAlsoKnownAs",
+        ),
+    ];
+    for (limits, expected) in cases {
+        let res = validate(&history, limits).unwrap_err();
+        assert_eq!(format!("{:?}", res.error), expected);
+    }
+
+    // checked up front: garbage beyond the size limit is not parsed at all
+    let mut oversized = history.clone();
+    oversized[2] = vec![0xff; 7501];
+    assert!(matches!(
+        validate(&oversized, Limits::default()),
+        Err(HistoryError {
+            index: 2,
+            stage: HistoryStage::Transition,
+            error: Error::OperationTooLarge,
+        })
+    ));
+    assert!(matches!(
+        validate(&oversized, Limits::UNLIMITED),
+        Err(HistoryError {
+            error: Error::NonCanonicalCbor,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_checkpoint_limits() {
+    let history = build_history();
+    let checkpoint = Cid::from_dag_cbor(&history[1]);
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+    let validate = |limits: Limits| {
+        validate_operation_history_from_checkpoint_with_limits(
+            &checkpoint,
+            to_cursors(&history[1..]),
+            vec![0, 1],
+            &msg,
+            &final_sig,
+            &limits,
+        )
+    };
+
+    assert!(validate(Limits::default()).is_ok());
+    assert!(validate(Limits::UNLIMITED).is_ok());
+
+    // the history length is counted from the checkpoint
+    assert!(
+        validate(Limits {
+            max_history_length: 2,
+            ..Limits::default()
+        })
+        .is_ok()
+    );
+    assert!(matches!(
+        validate(Limits {
+            max_history_length: 1,
+            ..Limits::default()
+        }),
+        Err(HistoryError {
+            index: 0,
+            stage: HistoryStage::History,
+            error: Error::HistoryTooLong,
+        })
+    ));
+    // the checkpoint operation itself is parsed within the limits
+    assert!(matches!(
+        validate(Limits {
+            max_also_known_as: 0,
+            ..Limits::default()
+        }),
+        Err(HistoryError {
+            index: 0,
+            stage: HistoryStage::Checkpoint,
+            error: Error::TooManyAlsoKnownAs,
+        })
+    ));
+    assert!(matches!(
+        validate(Limits {
+            max_operation_size: history[2].len() - 1,
+            ..Limits::default()
+        }),
+        Err(HistoryError {
+            index: 1,
+            stage: HistoryStage::Transition,
+            error: Error::OperationTooLarge,
+        })
+    ));
+}
+
+#[test]
+fn test_default_limits() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let update = OperationBuilder::update(&genesis)
+        .unwrap()
+        .also_known_as(
+            (0..11)
+                .map(|index| format!("at://user{index}.example.com"))
+                .collect(),
+        )
+        .sign(&rotation_key2)
+        .unwrap();
+    let history = vec![genesis.to_vec().unwrap(), update.to_vec().unwrap()];
+    let binary_did = genesis.get_binary_did().unwrap();
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+
+    assert!(matches!(
+        validate_operation_history(
+            &binary_did,
+            to_cursors(&history),
+            vec![0, 1, 1],
+            &msg,
+            &final_sig
+        ),
+        Err(HistoryError {
+            index: 1,
+            stage: HistoryStage::Transition,
+            error: Error::TooManyAlsoKnownAs,
+        })
+    ));
+    assert!(matches!(
+        find_rotation_key_indices(&binary_did, &history, &msg, &final_sig),
+        Err(Error::TooManyAlsoKnownAs)
+    ));
+}
@@ -0,0 +1,193 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::secp256r1_key;
+use crate::test_key_index::{build_history, to_cursors};
+use ckb_did_plc_utils::{
+    authorization::{authorization_message, local_id_authorization_message},
+    builder::OperationBuilder,
+    cid::Cid,
+    error::{Error, HistoryError, HistoryStage},
+    local_id::{LocalIdMethod, PlcAuthorization, PlcMethod},
+    operation::Operation,
+};
+use molecule::lazy_reader::Cursor;
+
+// Molecule `Bytes` and `Uint8Vec`: a fixvec of bytes
+fn fixvec(bytes: &[u8]) -> Vec<u8> {
+    let mut buf = (bytes.len() as u32).to_le_bytes().to_vec();
+    buf.extend_from_slice(bytes);
+    buf
+}
+
+// Molecule tables and dynvecs: the total size and offsets of the items, then the items
+fn dynvec(items: &[Vec<u8>]) -> Vec<u8> {
+    let header = 4 * (items.len() + 1);
+    let total = header + items.iter().map(Vec::len).sum::<usize>();
+    let mut buf = (total as u32).to_le_bytes().to_vec();
+    let mut offset = header;
+    for item in items {
+        buf.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += item.len();
+    }
+    for item in items {
+        buf.extend_from_slice(item);
+    }
+    buf
+}
+
+// Molecule `PlcAuthorization` of the witness
+fn plc_authorization(history: &[Vec<u8>], sig: &[u8], rotation_key_indices: &[u8]) -> Cursor {
+    let history: Vec<_> = history.iter().map(|op| fixvec(op)).collect();
+    Cursor::from(dynvec(&[
+        dynvec(&history),
+        fixvec(sig),
+        fixvec(rotation_key_indices),
+    ]))
+}
+
+#[test]
+fn test_plc_method() {
+    let history = build_history();
+    let genesis = Operation::from_slice(&history[0]).unwrap();
+    let local_id = genesis.get_did().unwrap();
+    assert!(local_id.starts_with(PlcMethod::PREFIX));
+    let id = PlcMethod::parse_id(&local_id).unwrap();
+    assert!(matches!(
+        PlcMethod::parse_id("did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme"),
+        Err(HistoryError {
+            stage: HistoryStage::History,
+            error: Error::InvalidDidFormat,
+            ..
+        })
+    ));
+
+    let type_id = [0x11u8; 20];
+    let tx_hash = [0x22u8; 32];
+    let msg = local_id_authorization_message(&local_id, &type_id, &tx_hash);
+    // the same message as the did:plc specific one
+    assert_eq!(
+        msg,
+        authorization_message(id.as_bytes(), &type_id, &tx_hash)
+    );
+    let sig = secp256r1_key().sign(&msg).unwrap();
+
+    let auth = PlcAuthorization {
+        history: to_cursors(&history),
+        rotation_key_indices: vec![0, 1, 0, 1],
+        sig: sig.clone(),
+        checkpoint: None,
+    };
+    let head = PlcMethod::verify(&id, auth, &msg).unwrap();
+    assert_eq!(head, Cid::from_dag_cbor(&history[2]));
+
+    // from a checkpoint, the indices of the operations before it are left out
+    let auth = PlcAuthorization {
+        history: to_cursors(&history[1..]),
+        rotation_key_indices: vec![0, 1],
+        sig: sig.clone(),
+        checkpoint: Some(Cid::from_dag_cbor(&history[1])),
+    };
+    assert_eq!(PlcMethod::verify(&id, auth, &msg).unwrap(), head);
+
+    let auth = PlcAuthorization {
+        history: to_cursors(&history),
+        rotation_key_indices: vec![0, 1, 0, 1],
+        sig,
+        checkpoint: None,
+    };
+    let other = local_id_authorization_message(&local_id, &[0x12u8; 20], &tx_hash);
+    assert!(matches!(
+        PlcMethod::verify(&id, auth, &other),
+        Err(HistoryError {
+            stage: HistoryStage::Final,
+            error: Error::VerifySignatureFailed,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_plc_method_authorization() {
+    let history = build_history();
+    let genesis = Operation::from_slice(&history[0]).unwrap();
+    let local_id = genesis.get_did().unwrap();
+    let id = PlcMethod::parse_id(&local_id).unwrap();
+    let msg = local_id_authorization_message(&local_id, &[0x11u8; 20], &[0x22u8; 32]);
+    let sig = secp256r1_key().sign(&msg).unwrap();
+
+    let witness = plc_authorization(&history, &sig, &[0, 1, 0, 1]);
+    let auth = PlcMethod::authorization(&witness, None).unwrap();
+    assert_eq!(auth.history.len(), history.len());
+    assert_eq!(auth.rotation_key_indices, vec![0, 1, 0, 1]);
+    assert_eq!(auth.sig, sig);
+    assert_eq!(auth.checkpoint, None);
+    let head = PlcMethod::verify(&id, auth, &msg).unwrap();
+    assert_eq!(head, Cid::from_dag_cbor(&history[2]));
+
+    let checkpoint = Cid::from_dag_cbor(&history[1]);
+    let witness = plc_authorization(&history[1..], &sig, &[0, 1]);
+    let auth = PlcMethod::authorization(&witness, Some(checkpoint)).unwrap();
+    assert_eq!(auth.checkpoint, Some(checkpoint));
+    assert_eq!(PlcMethod::verify(&id, auth, &msg).unwrap(), head);
+
+    // a table missing `rotation_key_indices`
+    let history: Vec<_> = history.iter().map(|op| fixvec(op)).collect();
+    let witness = Cursor::from(dynvec(&[dynvec(&history), fixvec(&sig)]));
+    assert!(matches!(
+        PlcMethod::authorization(&witness, None),
+        Err(HistoryError {
+            stage: HistoryStage::History,
+            error: Error::MoleculeError(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_plc_method_recovery() {
+    let history = build_history();
+    let genesis = Operation::from_slice(&history[0]).unwrap();
+    let local_id = genesis.get_did().unwrap();
+    let id = PlcMethod::parse_id(&local_id).unwrap();
+    let msg = local_id_authorization_message(&local_id, &[0x11u8; 20], &[0x22u8; 32]);
+    let sig = secp256r1_key().sign(&msg).unwrap();
+
+    // signed by the second rotation key after the first update, cancelled by the last
+    // operation of the history signed by the first one
+    let cancelled = OperationBuilder::update(&Operation::from_slice(&history[1]).unwrap())
+        .unwrap()
+        .also_known_as(vec!["at://mallory.example.com".to_string()])
+        .sign(&secp256r1_key())
+        .unwrap()
+        .to_vec()
+        .unwrap();
+    let checkpoint = Cid::from_dag_cbor(&cancelled);
+
+    // the recovered history from genesis replaces the checkpoint
+    let auth = PlcAuthorization {
+        history: to_cursors(&history),
+        rotation_key_indices: vec![0, 1, 0, 1],
+        sig: sig.clone(),
+        checkpoint: Some(checkpoint),
+    };
+    assert_eq!(
+        PlcMethod::verify(&id, auth, &msg).unwrap(),
+        Cid::from_dag_cbor(&history[2])
+    );
+
+    // a history not from genesis must start at the checkpoint
+    let auth = PlcAuthorization {
+        history: to_cursors(&history[1..]),
+        rotation_key_indices: vec![0, 1],
+        sig,
+        checkpoint: Some(checkpoint),
+    };
+    assert!(matches!(
+        PlcMethod::verify(&id, auth, &msg),
+        Err(HistoryError {
+            stage: HistoryStage::Checkpoint,
+            error: Error::CheckpointMismatched,
+            ..
+        })
+    ));
+}
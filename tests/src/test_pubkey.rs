@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{
+    base64::engine::{Engine, general_purpose::URL_SAFE_NO_PAD},
+    error::Error,
+    pubkey::{Curve, PublicKey},
+};
+
+#[test]
+fn test_public_key_conversions() {
+    for (private_key, curve, crv) in [
+        (secp256k1_key(1), Curve::Secp256k1, "secp256k1"),
+        (secp256r1_key(), Curve::Secp256r1, "P-256"),
+    ] {
+        let did_key = private_key.did_key();
+        let key = PublicKey::from_did_key(&did_key).unwrap();
+        assert_eq!(key.curve(), curve);
+        assert_eq!(key.raw().len(), 33);
+        assert_eq!(key.to_did_key(), did_key);
+        assert_eq!(format!("did:key:{}", key.to_multikey()), did_key);
+        assert_eq!(PublicKey::from_multikey(&key.to_multikey()).unwrap(), key);
+        assert_eq!(did_key.parse::<PublicKey>().unwrap(), key);
+
+        // compressed and uncompressed SEC1
+        let uncompressed = key.to_uncompressed().unwrap();
+        assert_eq!(uncompressed.len(), 65);
+        assert_eq!(uncompressed[0], 0x04);
+        assert_eq!(PublicKey::from_sec1_bytes(curve, key.raw()).unwrap(), key);
+        assert_eq!(
+            PublicKey::from_sec1_bytes(curve, &uncompressed).unwrap(),
+            key
+        );
+
+        let jwk = key.to_jwk().unwrap();
+        assert_eq!(jwk.kty, "EC");
+        assert_eq!(jwk.crv, crv);
+        assert_eq!(
+            URL_SAFE_NO_PAD.decode(&jwk.x).unwrap(),
+            &uncompressed[1..33]
+        );
+        assert_eq!(URL_SAFE_NO_PAD.decode(&jwk.y).unwrap(), &uncompressed[33..]);
+        assert_eq!(
+            jwk.to_json(),
+            format!(
+                r#"{{"kty":"EC","crv":"{crv}","x":"{}","y":"{}"}}"#,
+                jwk.x, jwk.y
+            )
+        );
+    }
+}
+
+#[test]
+fn test_invalid_public_key() {
+    let key = PublicKey::from_did_key(&secp256k1_key(1).did_key()).unwrap();
+    // not on the curve
+    let mut not_on_curve = vec![0u8; 65];
+    not_on_curve[0] = 0x04;
+    not_on_curve[32] = 1;
+    not_on_curve[64] = 1;
+    for curve in [Curve::Secp256k1, Curve::Secp256r1] {
+        assert!(matches!(
+            PublicKey::from_sec1_bytes(curve, &not_on_curve),
+            Err(Error::InvalidKey)
+        ));
+        assert!(matches!(
+            PublicKey::from_sec1_bytes(curve, &[0x04; 64]),
+            Err(Error::InvalidKey)
+        ));
+    }
+    // too short to carry a multicodec prefix
+    assert!(matches!(
+        PublicKey::from_did_key("did:key:z"),
+        Err(Error::InvalidKey)
+    ));
+    assert!(matches!(
+        PublicKey::from_did_key("did:key:z2"),
+        Err(Error::InvalidKey)
+    ));
+    assert!(matches!(
+        PublicKey::from_multikey(&key.to_did_key()),
+        Err(Error::InvalidKey)
+    ));
+}
@@ -0,0 +1,123 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::common::{decode_map, encode, set_field, text};
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{cbor4ii::core::Value, error::Error, operation::Operation};
+
+// decodes the genesis operation, lets `f` modify the top-level map, then validates it
+fn validate_modified(f: impl FnOnce(&mut Vec<(Value, Value)>)) -> Result<(), Error> {
+    let buf = genesis_operation(&secp256k1_key(1), &secp256r1_key())
+        .to_vec()
+        .unwrap();
+    let mut pairs = decode_map(&buf);
+    f(&mut pairs);
+    Operation::from_slice(&encode(&Value::Map(pairs)))?.validate()
+}
+
+fn rotation_keys(count: u8) -> Value {
+    Value::Array(
+        (0..count)
+            .map(|i| text(&secp256k1_key(i).did_key()))
+            .collect(),
+    )
+}
+
+#[test]
+fn test_schema_valid() {
+    assert!(validate_modified(|_| {}).is_ok());
+    assert!(validate_modified(|pairs| set_field(pairs, "rotationKeys", rotation_keys(5))).is_ok());
+    assert!(validate_modified(|pairs| set_field(pairs, "services", Value::Map(vec![]))).is_ok());
+    assert!(
+        validate_modified(|pairs| set_field(pairs, "alsoKnownAs", Value::Array(vec![]))).is_ok()
+    );
+}
+
+#[test]
+fn test_schema_fields() {
+    let res = validate_modified(|pairs| pairs.retain(|(k, _)| *k != text("alsoKnownAs")));
+    assert!(matches!(res, Err(Error::MissingField)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "extra", text("value")));
+    assert!(matches!(res, Err(Error::UnexpectedField)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "type", text("plc_unknown")));
+    assert!(matches!(res, Err(Error::InvalidOperation)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "sig", Value::Bytes(vec![0; 64])));
+    assert!(matches!(res, Err(Error::InvalidSignature)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "prev", Value::Integer(1)));
+    assert!(matches!(res, Err(Error::InvalidPrevType)));
+
+    // a tombstone always has a prev
+    let res = validate_modified(|pairs| {
+        pairs.retain(|(k, _)| [text("type"), text("prev"), text("sig")].contains(k));
+        set_field(pairs, "type", text("plc_tombstone"));
+    });
+    assert!(matches!(res, Err(Error::InvalidPrevType)));
+}
+
+#[test]
+fn test_schema_rotation_keys() {
+    let res = validate_modified(|pairs| set_field(pairs, "rotationKeys", text("not-an-array")));
+    assert!(matches!(res, Err(Error::RotationKeysDecodeError)));
+
+    let res = validate_modified(|pairs| {
+        set_field(pairs, "rotationKeys", Value::Array(vec![Value::Integer(1)]))
+    });
+    assert!(matches!(res, Err(Error::RotationKeysDecodeError)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "rotationKeys", rotation_keys(0)));
+    assert!(matches!(res, Err(Error::InvalidRotationKeysCount)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "rotationKeys", rotation_keys(6)));
+    assert!(matches!(res, Err(Error::InvalidRotationKeysCount)));
+
+    let res = validate_modified(|pairs| {
+        let key = text(&secp256k1_key(1).did_key());
+        set_field(pairs, "rotationKeys", Value::Array(vec![key.clone(), key]))
+    });
+    assert!(matches!(res, Err(Error::DuplicatedRotationKey)));
+
+    let res = validate_modified(|pairs| {
+        set_field(
+            pairs,
+            "rotationKeys",
+            Value::Array(vec![text("did:key:zabc")]),
+        )
+    });
+    assert!(matches!(res, Err(Error::InvalidKey)));
+}
+
+#[test]
+fn test_schema_document_fields() {
+    let res = validate_modified(|pairs| {
+        set_field(
+            pairs,
+            "verificationMethods",
+            Value::Map(vec![(text("atproto"), text("https://example.com"))]),
+        )
+    });
+    assert!(matches!(res, Err(Error::InvalidVerificationMethods)));
+
+    let res =
+        validate_modified(|pairs| set_field(pairs, "verificationMethods", Value::Array(vec![])));
+    assert!(matches!(res, Err(Error::InvalidVerificationMethods)));
+
+    let res =
+        validate_modified(|pairs| set_field(pairs, "alsoKnownAs", Value::Array(vec![Value::Null])));
+    assert!(matches!(res, Err(Error::InvalidAlsoKnownAs)));
+
+    let res = validate_modified(|pairs| set_field(pairs, "services", Value::Integer(1)));
+    assert!(matches!(res, Err(Error::InvalidServices)));
+
+    let res = validate_modified(|pairs| {
+        let service = Value::Map(vec![(text("type"), text("AtprotoPersonalDataServer"))]);
+        set_field(
+            pairs,
+            "services",
+            Value::Map(vec![(text("atproto_pds"), service)]),
+        )
+    });
+    assert!(matches!(res, Err(Error::InvalidServices)));
+}
@@ -0,0 +1,70 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use ckb_did_plc_utils::{cid::Cid, document::DidDocument, operation::Operation, pubkey::PublicKey};
+
+#[test]
+fn test_serde_operation() {
+    let genesis = genesis_operation(&secp256k1_key(1), &secp256r1_key());
+    // the same JSON as the PLC directory
+    let json = serde_json::to_string(&genesis).unwrap();
+    assert_eq!(json, genesis.to_json().unwrap());
+
+    let decoded: Operation = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.to_vec().unwrap(), genesis.to_vec().unwrap());
+    assert_eq!(
+        decoded.generate_cid().unwrap(),
+        genesis.generate_cid().unwrap()
+    );
+
+    // keys are sorted as DAG-CBOR, whatever the order in JSON
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let decoded: Operation = serde_json::from_value(value).unwrap();
+    assert_eq!(decoded.to_vec().unwrap(), genesis.to_vec().unwrap());
+
+    for json in [r#"{"a":1,"a":2}"#, r#"{"a":1.5}"#, r#"[1]"#] {
+        assert!(serde_json::from_str::<Operation>(json).is_err(), "{json}");
+    }
+}
+
+#[test]
+fn test_serde_links() {
+    let cid = Cid::from_dag_cbor(b"test");
+    // bytes and CID links as in DAG-JSON
+    let json = format!(r#"{{"cid":{{"/":"{cid}"}},"bytes":{{"/":{{"bytes":"AQID"}}}}}}"#);
+    let op: Operation = serde_json::from_str(&json).unwrap();
+    // "cid" is shorter, so first in DAG-CBOR order
+    assert_eq!(
+        serde_json::to_string(&op).unwrap(),
+        format!(r#"{{"cid":{{"/":"{cid}"}},"bytes":{{"/":{{"bytes":"AQID"}}}}}}"#)
+    );
+    let cbor = op.to_vec().unwrap();
+    // tag 42, bytes with the identity multibase prefix
+    assert!(cbor.windows(3).any(|w| w == [0xd8, 0x2a, 0x58]));
+    assert!(serde_json::from_str::<Operation>(r#"{"a":{"/":"not a cid"}}"#).is_err());
+}
+
+#[test]
+fn test_serde_cid_and_key() {
+    let cid = Cid::from_dag_cbor(b"test");
+    let json = serde_json::to_string(&cid).unwrap();
+    assert_eq!(json, format!("\"{cid}\""));
+    assert_eq!(serde_json::from_str::<Cid>(&json).unwrap(), cid);
+    assert!(serde_json::from_str::<Cid>("\"bafy\"").is_err());
+
+    let did_key = secp256r1_key().did_key();
+    let key = PublicKey::from_did_key(&did_key).unwrap();
+    let json = serde_json::to_string(&key).unwrap();
+    assert_eq!(json, format!("\"{did_key}\""));
+    assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), key);
+    assert!(serde_json::from_str::<PublicKey>("\"did:key:z\"").is_err());
+}
+
+#[test]
+fn test_serde_document() {
+    let genesis = genesis_operation(&secp256k1_key(1), &secp256r1_key());
+    let doc = DidDocument::from_operation(&genesis.get_did().unwrap(), &genesis).unwrap();
+    let json = serde_json::to_string(&doc).unwrap();
+    assert_eq!(json, doc.to_json());
+    assert_eq!(serde_json::from_str::<DidDocument>(&json).unwrap(), doc);
+}
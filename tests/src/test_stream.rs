@@ -0,0 +1,314 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::common::{decode_map, encode, set_field, sort_keys, text};
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_key_index::{build_history, to_cursors};
+use crate::test_legacy::legacy_genesis_operation;
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    cbor4ii::core::Value,
+    error::{Error, HistoryError, HistoryStage},
+    operation::{Operation, OperationType, validate_operation_history},
+    stream::StreamedOperation,
+};
+use molecule::lazy_reader::Cursor;
+
+fn stream(buf: &[u8]) -> Result<StreamedOperation, Error> {
+    StreamedOperation::from_cursor(&Cursor::from(buf.to_vec()))
+}
+
+// modifies the top-level map of the genesis operation, keeping it canonical DAG-CBOR
+fn modified(f: impl FnOnce(&mut Vec<(Value, Value)>)) -> Vec<u8> {
+    let buf = genesis_operation(&secp256k1_key(1), &secp256r1_key())
+        .to_vec()
+        .unwrap();
+    let mut pairs = decode_map(&buf);
+    f(&mut pairs);
+    sort_keys(&mut pairs);
+    encode(&Value::Map(pairs))
+}
+
+#[test]
+fn test_streamed_operation() {
+    let rotation_key1 = secp256k1_key(1);
+    let rotation_key2 = secp256r1_key();
+    let genesis = genesis_operation(&rotation_key1, &rotation_key2);
+    let tombstone = OperationBuilder::tombstone(&genesis)
+        .unwrap()
+        .sign(&rotation_key2)
+        .unwrap();
+    let legacy = Operation::from_slice(&legacy_genesis_operation(
+        &rotation_key1,
+        &rotation_key2,
+        "alice.test",
+        "https://example.com",
+    ))
+    .unwrap();
+
+    for op in [&genesis, &tombstone, &legacy] {
+        let buf = op.to_vec().unwrap();
+        let streamed = stream(&buf).unwrap();
+        assert_eq!(streamed.r#type, op.get_type().unwrap());
+        assert_eq!(streamed.prev, op.get_prev().unwrap());
+        assert_eq!(streamed.cid, op.generate_cid().unwrap());
+        assert_eq!(streamed.binary_did(), op.get_binary_did().unwrap());
+        assert_eq!(
+            streamed.get_signature().unwrap(),
+            op.get_signature().unwrap()
+        );
+    }
+
+    let streamed = stream(&genesis.to_vec().unwrap()).unwrap();
+    let rotation_keys: Vec<String> = streamed
+        .rotation_keys
+        .iter()
+        .map(|key| key.to_did_key())
+        .collect();
+    assert_eq!(
+        rotation_keys,
+        vec![rotation_key1.did_key(), rotation_key2.did_key()]
+    );
+    assert!(
+        stream(&tombstone.to_vec().unwrap())
+            .unwrap()
+            .rotation_keys
+            .is_empty()
+    );
+    // legacy rotation keys are in map order: signingKey, recoveryKey
+    assert_eq!(
+        stream(&legacy.to_vec().unwrap())
+            .unwrap()
+            .rotation_keys
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_streamed_operation_errors() {
+    let cases: Vec<(&str, Vec<u8>)> = vec![
+        (
+            "missing field",
+            modified(|pairs| pairs.retain(|(k, _)| *k != text("alsoKnownAs"))),
+        ),
+        (
+            "unexpected field",
+            modified(|pairs| set_field(pairs, "extra", text("value"))),
+        ),
+        (
+            "unknown type",
+            modified(|pairs| set_field(pairs, "type", text("plc_unknown"))),
+        ),
+        (
+            "sig not text",
+            modified(|pairs| set_field(pairs, "sig", Value::Bytes(vec![0; 64]))),
+        ),
+        (
+            "prev not text",
+            modified(|pairs| set_field(pairs, "prev", Value::Integer(1))),
+        ),
+        (
+            "invalid prev",
+            modified(|pairs| set_field(pairs, "prev", text("bafy"))),
+        ),
+        (
+            "rotation keys not array",
+            modified(|pairs| set_field(pairs, "rotationKeys", text("not-an-array"))),
+        ),
+        (
+            "rotation key not text",
+            modified(|pairs| {
+                set_field(pairs, "rotationKeys", Value::Array(vec![Value::Integer(1)]))
+            }),
+        ),
+        (
+            "no rotation keys",
+            modified(|pairs| set_field(pairs, "rotationKeys", Value::Array(vec![]))),
+        ),
+        (
+            "duplicated rotation keys",
+            modified(|pairs| {
+                let key = text(&secp256k1_key(1).did_key());
+                set_field(pairs, "rotationKeys", Value::Array(vec![key.clone(), key]))
+            }),
+        ),
+        (
+            "invalid rotation key",
+            modified(|pairs| {
+                set_field(
+                    pairs,
+                    "rotationKeys",
+                    Value::Array(vec![text("did:key:zabc")]),
+                )
+            }),
+        ),
+        (
+            "invalid verification method",
+            modified(|pairs| {
+                set_field(
+                    pairs,
+                    "verificationMethods",
+                    Value::Map(vec![(text("atproto"), text("https://example.com"))]),
+                )
+            }),
+        ),
+        (
+            "invalid also known as",
+            modified(|pairs| set_field(pairs, "alsoKnownAs", Value::Array(vec![Value::Null]))),
+        ),
+        (
+            "services not map",
+            modified(|pairs| set_field(pairs, "services", Value::Integer(1))),
+        ),
+        (
+            "service without endpoint",
+            modified(|pairs| {
+                let service = Value::Map(vec![(text("type"), text("AtprotoPersonalDataServer"))]);
+                set_field(
+                    pairs,
+                    "services",
+                    Value::Map(vec![(text("atproto_pds"), service)]),
+                )
+            }),
+        ),
+        (
+            "tombstone without prev",
+            modified(|pairs| {
+                pairs.retain(|(k, _)| [text("type"), text("prev"), text("sig")].contains(k));
+                set_field(pairs, "type", text("plc_tombstone"));
+            }),
+        ),
+        ("not a map", vec![0x80]),
+        ("truncated", vec![0xa1, 0x63, b's', b'i']),
+        // {"sig": 1.5}
+        (
+            "float",
+            vec![0xa1, 0x63, b's', b'i', b'g', 0xf9, 0x3e, 0x00],
+        ),
+        // {"b": 1, "a": 2}
+        (
+            "unsorted keys",
+            vec![0xa2, 0x61, b'b', 0x01, 0x61, b'a', 0x02],
+        ),
+    ];
+    for (label, buf) in cases {
+        let expected = Operation::from_slice_strict(&buf).and_then(|op| op.validate());
+        let res = stream(&buf);
+        assert!(expected.is_err(), "[{label}] expect an error");
+        assert_eq!(
+            format!("{:?}", res.err()),
+            format!("{:?}", expected.err()),
+            "[{label}]"
+        );
+    }
+
+    // trailing bytes
+    let mut buf = genesis_operation(&secp256k1_key(1), &secp256r1_key())
+        .to_vec()
+        .unwrap();
+    buf.push(0x00);
+    assert!(matches!(stream(&buf), Err(Error::NonCanonicalCbor)));
+
+    // tag 42 takes bytes starting with 0x00, even where the field is skipped
+    for content in [Value::Integer(1), text("x"), Value::Bytes(vec![0x01])] {
+        let buf = modified(|pairs| set_field(pairs, "sig", Value::Tag(42, Box::new(content))));
+        assert!(matches!(stream(&buf), Err(Error::NonCanonicalCbor)));
+    }
+}
+
+#[test]
+fn test_streamed_history() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+    let validate = |history: &[Vec<u8>], binary_did: &[u8], indices: Vec<usize>| {
+        validate_operation_history(binary_did, to_cursors(history), indices, &msg, &final_sig)
+            .map_err(Error::from)
+    };
+
+    assert!(validate(&history, &binary_did, vec![0, 1, 0, 1]).is_ok());
+    assert!(matches!(
+        validate(&history, &binary_did, vec![0, 0, 0, 1]),
+        Err(Error::VerifySignatureFailed)
+    ));
+    assert!(matches!(
+        validate(&history, &binary_did, vec![0, 1, 0, 2]),
+        Err(Error::InvalidKeyIndex)
+    ));
+    assert!(matches!(
+        validate(&history, &[0u8; 15], vec![0, 1, 0, 1]),
+        Err(Error::DidMismatched)
+    ));
+    assert!(matches!(
+        validate(&history[1..], &binary_did, vec![1, 0, 1]),
+        Err(Error::NotGenesisOperation)
+    ));
+    let skipped = vec![history[0].clone(), history[2].clone()];
+    assert!(matches!(
+        validate(&skipped, &binary_did, vec![0, 0, 1]),
+        Err(Error::InvalidPrev)
+    ));
+}
+
+#[test]
+fn test_history_error_position() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+    let validate = |history: &[Vec<u8>], indices: Vec<usize>| {
+        validate_operation_history(&binary_did, to_cursors(history), indices, &msg, &final_sig)
+            .unwrap_err()
+    };
+
+    let cases = [
+        (
+            validate(&history, vec![0, 1]),
+            0,
+            HistoryStage::History,
+            "history: InvalidHistory",
+        ),
+        (
+            validate(&history, vec![1, 1, 0, 1]),
+            0,
+            HistoryStage::Genesis,
+            "genesis operation: VerifySignatureFailed",
+        ),
+        (
+            validate(&history, vec![0, 1, 1, 1]),
+            2,
+            HistoryStage::Transition,
+            "transition 1 -> 2: VerifySignatureFailed",
+        ),
+        (
+            validate(&history, vec![0, 1, 0, 0]),
+            2,
+            HistoryStage::Final,
+            "final authorization with operation 2: VerifySignatureFailed",
+        ),
+    ];
+    for (error, index, stage, display) in cases {
+        assert_eq!(error.index, index, "{display}");
+        assert_eq!(error.stage, stage, "{display}");
+        assert_eq!(error.to_string(), display);
+    }
+
+    let mut broken = history.clone();
+    broken[1].truncate(10);
+    let error = validate(&broken, vec![0, 1, 0, 1]);
+    assert!(matches!(
+        error,
+        HistoryError {
+            index: 1,
+            stage: HistoryStage::Transition,
+            error: Error::InvalidCbor,
+        }
+    ));
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
+use crate::common::{decode_map, encode, set_field, text};
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
 use ckb_did_plc_utils::{
     base32::{self, Alphabet},
     base64::{
@@ -12,12 +14,12 @@ use ckb_did_plc_utils::{
         enc::Encode,
         utils::{BufWriter, SliceReader},
     },
-    error::Error,
+    error::{Error, HistoryError},
     operation::{
-        Operation, parse_local_id, validate_2_operations, validate_genesis_operation,
-        validate_operation_history,
+        Operation, OperationType, Service, parse_local_id, validate_2_operations,
+        validate_genesis_operation, validate_operation_history,
     },
-    pubkey::decode_base58btc,
+    pubkey::{Curve, decode_base58btc},
     reader::validate_cbor_format,
 };
 use molecule::lazy_reader::{Cursor, Error as MoleculeError, Read};
@@ -79,14 +81,20 @@ fn test_vectors_5_6() {
 
 #[test]
 fn test_vectors_6_7() {
-    // tombstone is not allowed
-    let prev_path = get_test_vector_path("6-update-handle.cbor");
-    let cur_path = get_test_vector_path("7-tombstone.cbor");
+    test_one_vector("6-update-handle.cbor", "7-tombstone.cbor", 0);
+}
+
+#[test]
+fn test_vectors_after_tombstone() {
+    // nothing can follow a tombstone
+    let prev_path = get_test_vector_path("7-tombstone.cbor");
+    let cur_path = get_test_vector_path("6-update-handle.cbor");
     let prev_buf = read(&prev_path).unwrap_or_else(|_| panic!("Failed to read {}", prev_path));
     let cur_buf = read(&cur_path).unwrap_or_else(|_| panic!("Failed to read {}", cur_path));
     let result = validate_2_operations(&prev_buf, &cur_buf, 0);
-    assert!(result.is_err());
+    assert!(matches!(result, Err(Error::DidTombstoned)));
 }
+
 #[test]
 fn test_vector_legacy_1_2() {
     test_one_vector(
@@ -107,6 +115,7 @@ fn test_genesis_operation() {
 }
 
 #[test]
+#[allow(clippy::expect_fun_call, clippy::useless_vec)]
 fn test_genesis_operation_wrong_did() {
     let genesis_path = get_test_vector_path("1-did-creation.cbor");
     let genesis_buf = read(&genesis_path).expect(&format!("Failed to read {}", genesis_path));
@@ -326,7 +335,13 @@ fn test_utils_error_invalid_history() {
         validate_operation_history(&binary_did, history, rotation_key_indices, &msg, &final_sig);
 
     // Step 2: Verify returns UtilsError::InvalidHistory
-    assert!(matches!(result, Err(Error::InvalidHistory)));
+    assert!(matches!(
+        result,
+        Err(HistoryError {
+            error: Error::InvalidHistory,
+            ..
+        })
+    ));
 
     // Additional test: History length does not match indices
     let history = vec![Cursor::new(
@@ -339,7 +354,13 @@ fn test_utils_error_invalid_history() {
     let rotation_key_indices = vec![0]; // Length should be history.len() + 1 = 2
     let result2 =
         validate_operation_history(&binary_did, history, rotation_key_indices, &msg, &final_sig);
-    assert!(matches!(result2, Err(Error::InvalidHistory)));
+    assert!(matches!(
+        result2,
+        Err(HistoryError {
+            error: Error::InvalidHistory,
+            ..
+        })
+    ));
 }
 
 #[test]
@@ -408,7 +429,13 @@ fn test_utils_error_tombstone_in_history() {
     let result =
         validate_operation_history(&binary_did, history, rotation_key_indices, &msg, &final_sig);
 
-    assert!(result.is_err());
+    assert!(matches!(
+        result,
+        Err(HistoryError {
+            error: Error::DidTombstoned,
+            ..
+        })
+    ));
 }
 
 pub fn set_rotation_keys_to_string(buf: &[u8]) -> Vec<u8> {
@@ -513,7 +540,7 @@ fn test_rotation_keys_decode_error() {
         let op = Operation::from_slice(&prev_bad).expect("decode prev_bad");
         op.generate_cid().expect("cid")
     };
-    let cur_patched = set_prev_field(&cur, &new_cid);
+    let cur_patched = set_prev_field(&cur, &new_cid.to_string());
     let res = validate_2_operations(&prev_bad, &cur_patched, 0);
     assert!(matches!(res, Err(Error::RotationKeysDecodeError)));
 }
@@ -524,10 +551,11 @@ fn test_invalid_key() {
     let cur = read(get_test_vector_path("2-update-handle.cbor")).unwrap();
 
     {
+        // the schema of previous operation is validated before the CID link
         let prev_bad = set_rotation_keys_to_string(&prev);
         let res = validate_2_operations(&prev_bad, &cur, 0);
-        eprintln!("[case0] expect InvalidPrev -> got: {res:?}");
-        assert!(matches!(res, Err(Error::InvalidPrev)));
+        eprintln!("[case0] expect RotationKeysDecodeError -> got: {res:?}");
+        assert!(matches!(res, Err(Error::RotationKeysDecodeError)));
     }
 
     // Helper to run a case that expects InvalidKey:
@@ -538,7 +566,7 @@ fn test_invalid_key() {
             .generate_cid()
             .unwrap();
         // Patch cur.prev to pass the chain check
-        let cur_patched = set_prev_field(&cur, &new_cid);
+        let cur_patched = set_prev_field(&cur, &new_cid.to_string());
         // Now the pipeline can reach key parsing / verification parts
         let res = validate_2_operations(&prev_bad, &cur_patched, 0);
         eprintln!("[{label}] expect InvalidKey -> got: {res:?}");
@@ -630,7 +658,7 @@ fn test_invalid_key() {
             .unwrap()
             .generate_cid()
             .unwrap();
-        let cur_patched = set_prev_field(&cur, &new_cid);
+        let cur_patched = set_prev_field(&cur, &new_cid.to_string());
 
         let res = validate_2_operations(&prev_good, &cur_patched, 0);
         eprintln!("[case6_valid_key_roundtrip] expect Ok(()) -> got: {res:?}");
@@ -779,3 +807,57 @@ fn test_invalid_signature_padding() {
         assert!(matches!(res, Err(Error::InvalidSignaturePadding)));
     }
 }
+
+#[test]
+fn test_operation_accessors() {
+    let buf = read(get_test_vector_path("1-did-creation.cbor")).unwrap();
+    let op = Operation::from_slice(&buf).unwrap();
+    assert_eq!(op.get_type().unwrap(), OperationType::PlcOperation);
+    assert_eq!(op.get_prev().unwrap(), None);
+    assert_eq!(op.get_did().unwrap(), load_did("creation"));
+
+    let rotation_keys = op.get_rotation_keys().unwrap();
+    assert_eq!(rotation_keys.len(), 2);
+    assert_eq!(rotation_keys[0].curve(), Curve::Secp256k1);
+    assert_eq!(rotation_keys[1].curve(), Curve::Secp256r1);
+
+    let methods = op.get_verification_methods().unwrap();
+    assert!(methods["atproto"].starts_with("did:key:z"));
+    assert_eq!(
+        op.get_also_known_as().unwrap(),
+        vec!["at://alice.example.com"]
+    );
+    let services = op.get_services().unwrap();
+    assert_eq!(
+        services["atproto_pds"],
+        Service {
+            r#type: "AtprotoPersonalDataServer".to_string(),
+            endpoint: "https://example.com".to_string(),
+        }
+    );
+
+    let buf = read(get_test_vector_path("7-tombstone.cbor")).unwrap();
+    let op = Operation::from_slice(&buf).unwrap();
+    assert_eq!(op.get_type().unwrap(), OperationType::PlcTombstone);
+    assert!(op.get_prev().unwrap().is_some());
+    assert!(matches!(op.get_services(), Err(Error::InvalidOperation)));
+
+    let buf = read(get_test_vector_path("1-did-creation-legacy.cbor")).unwrap();
+    let op = Operation::from_slice(&buf).unwrap();
+    assert_eq!(op.get_type().unwrap(), OperationType::Create);
+    assert_eq!(op.get_legacy_rotation_keys().unwrap().len(), 2);
+}
+
+#[test]
+fn test_rotation_keys_non_text_item() {
+    let genesis = genesis_operation(&secp256k1_key(1), &secp256r1_key());
+    let mut pairs = decode_map(&genesis.to_vec().unwrap());
+    let rotation_keys = vec![text(&secp256k1_key(1).did_key()), Value::Integer(1)];
+    set_field(&mut pairs, "rotationKeys", Value::Array(rotation_keys));
+
+    let op = Operation::from_slice(&encode(&Value::Map(pairs))).unwrap();
+    assert!(matches!(
+        op.get_rotation_keys(),
+        Err(Error::RotationKeysDecodeError)
+    ));
+}
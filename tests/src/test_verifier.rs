@@ -0,0 +1,128 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use crate::test_builder::{genesis_operation, secp256k1_key, secp256r1_key};
+use crate::test_key_index::build_history;
+use ckb_did_plc_utils::{
+    builder::OperationBuilder,
+    error::Error,
+    operation::{Operation, find_rotation_key_indices},
+    verifier::HistoryVerifier,
+};
+
+#[test]
+fn test_history_verifier() {
+    let history = build_history();
+    let genesis = Operation::from_slice(&history[0]).unwrap();
+    let binary_did = genesis.get_binary_did().unwrap();
+
+    let mut verifier = HistoryVerifier::new(&binary_did, &history[0]).unwrap();
+    assert_eq!(verifier.did(), genesis.get_did().unwrap());
+    assert_eq!(verifier.key_index(), 0);
+    assert_eq!(verifier.head_cid(), genesis.generate_cid().unwrap());
+    let mut indices = vec![verifier.key_index()];
+    for op in &history[1..] {
+        let (key_index, cid) = verifier.push(op).unwrap();
+        assert_eq!(
+            cid,
+            Operation::from_slice(op).unwrap().generate_cid().unwrap()
+        );
+        assert_eq!(verifier.head_cid(), cid);
+        assert_eq!(verifier.head(), op.as_slice());
+        indices.push(key_index);
+    }
+    assert_eq!(verifier.history_len(), 3);
+    // the same as verifying the whole history at once
+    let msg = [0x42u8; 32];
+    let final_sig = secp256r1_key().sign(&msg).unwrap();
+    let mut expected = find_rotation_key_indices(&binary_did, &history, &msg, &final_sig).unwrap();
+    expected.pop();
+    assert_eq!(indices, expected);
+
+    let head = Operation::from_slice(history.last().unwrap()).unwrap();
+    assert_eq!(verifier.rotation_keys(), head.get_rotation_keys().unwrap());
+    let document = verifier.document().unwrap();
+    assert_eq!(document.id, verifier.did());
+    assert_eq!(document.also_known_as, vec!["at://bob.example.com"]);
+}
+
+#[test]
+fn test_history_verifier_resume() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    let mut verifier = HistoryVerifier::new(&binary_did, &history[0]).unwrap();
+    verifier.push(&history[1]).unwrap();
+
+    let state = verifier.to_bytes().unwrap();
+    let mut resumed = HistoryVerifier::from_bytes(&state).unwrap();
+    assert_eq!(resumed.to_bytes().unwrap(), state);
+    assert_eq!(resumed.history_len(), 2);
+    assert_eq!(resumed.key_index(), 1);
+    assert_eq!(resumed.head_cid(), verifier.head_cid());
+    assert_eq!(
+        resumed.push(&history[2]).unwrap(),
+        verifier.push(&history[2]).unwrap()
+    );
+
+    assert!(matches!(
+        HistoryVerifier::from_bytes(&[0xa0]),
+        Err(Error::MissingField)
+    ));
+    assert!(matches!(
+        HistoryVerifier::from_bytes(&[0x80]),
+        Err(Error::InvalidCbor)
+    ));
+}
+
+#[test]
+fn test_history_verifier_errors() {
+    let history = build_history();
+    let binary_did = Operation::from_slice(&history[0])
+        .unwrap()
+        .get_binary_did()
+        .unwrap();
+    assert!(matches!(
+        HistoryVerifier::new(&[0u8; 15], &history[0]),
+        Err(Error::DidMismatched)
+    ));
+    assert!(matches!(
+        HistoryVerifier::new(&binary_did, &history[1]),
+        Err(Error::NotGenesisOperation)
+    ));
+
+    let mut verifier = HistoryVerifier::new(&binary_did, &history[0]).unwrap();
+    // skipping an operation
+    assert!(matches!(
+        verifier.push(&history[2]),
+        Err(Error::InvalidPrev)
+    ));
+    // a failed push leaves the state unchanged
+    assert_eq!(verifier.history_len(), 1);
+    verifier.push(&history[1]).unwrap();
+
+    // signed by a key which is not a rotation key
+    let update = Operation::from_slice(&history[1]).unwrap();
+    let forged = OperationBuilder::update(&update)
+        .unwrap()
+        .sign(&secp256k1_key(9))
+        .unwrap();
+    assert!(matches!(
+        verifier.push(&forged.to_vec().unwrap()),
+        Err(Error::VerifySignatureFailed)
+    ));
+
+    let tombstone = OperationBuilder::tombstone(&update)
+        .unwrap()
+        .sign(&secp256r1_key())
+        .unwrap();
+    verifier.push(&tombstone.to_vec().unwrap()).unwrap();
+    assert!(verifier.is_tombstoned());
+    assert!(verifier.rotation_keys().is_empty());
+    assert!(matches!(verifier.document(), Err(Error::DidTombstoned)));
+    assert!(matches!(
+        verifier.push(&history[2]),
+        Err(Error::DidTombstoned)
+    ));
+}
@@ -3,6 +3,7 @@ import {
   hexFrom,
   Transaction,
   Hex,
+  HexLike,
   hashTypeId,
   bytesFrom,
   WitnessArgs,
@@ -71,6 +72,13 @@ async function main(
     invalidCbor?: boolean;
     mismatchedHistory?: boolean;
     moleculeCompatible?: boolean;
+    // cell data in DidWeb5DataV2, with `plcHead` in the output and `prevPlcHead` in
+    // the input of an update
+    dataV2?: boolean;
+    plcHead?: Hex;
+    prevPlcHead?: Hex;
+    // an update without local id authorization in the witness
+    noWitness?: boolean;
   },
   shouldFail?: boolean,
 ): Promise<number> {
@@ -84,6 +92,7 @@ async function main(
   );
   let transferredFrom: Hex | null = null;
   let codeHashToRun: Hex | null = null;
+  let typeArgs: Hex = "0x";
 
   // cell data
   if (config?.noAssociatePlc) {
@@ -94,22 +103,31 @@ async function main(
   // When testing invalid CBOR scenarios, use "0x82" which represents a CBOR array
   // expecting 2 elements but provides none, making it invalid CBOR format
   let cborData = config?.invalidCbor ? bytesFrom("0x82") : cbor.encode("");
-  let didWeb5Data = molecule.DidWeb5Data.from({
-    value: {
-      document: cborData,
-      localId: transferredFrom,
-    },
-  });
+  const cellData = (
+    document: HexLike,
+    localId: Hex | null,
+    plcHead?: Hex,
+  ): molecule.DidWeb5Data =>
+    config?.dataV2
+      ? molecule.DidWeb5Data.from({
+          type: "DidWeb5DataV2",
+          value: { document, localId, plcHead },
+        })
+      : molecule.DidWeb5Data.from({ value: { document, localId } });
+  let didWeb5Data = cellData(cborData, transferredFrom, config?.plcHead);
 
   if (config?.update || config?.updateLocalId) {
     // script args
     let typeScript = script.clone();
     typeScript.args = hexFrom("0x" + "0".repeat(40));
+    typeArgs = typeScript.args;
     codeHashToRun = typeScript.hash();
     const inputCell = resource.mockCell(
       alwaysSuccessScript,
       typeScript,
-      hexFrom(didWeb5Data.toBytes()),
+      hexFrom(
+        cellData(cborData, transferredFrom, config?.prevPlcHead).toBytes(),
+      ),
     );
     // input cells
     for (let i = 0; i < (config?.inputCellCount ?? 1); i++) {
@@ -121,14 +139,13 @@ async function main(
       tx.outputs.push(
         Resource.createCellOutput(alwaysSuccessScript, typeScript),
       );
-      let newDidWeb5Data = didWeb5Data.clone();
-      if (config?.updateLocalId) {
-        newDidWeb5Data.value.localId = hexFrom(newLocalId("0x00"));
-      } else {
-        newDidWeb5Data.value.document = hexFrom(
-          cbor.encode({ key: "hello, world" }),
-        );
-      }
+      let newDidWeb5Data = config?.updateLocalId
+        ? cellData(cborData, newLocalId("0x00"), config?.plcHead)
+        : cellData(
+            cbor.encode({ key: "hello, world" }),
+            transferredFrom,
+            config?.plcHead,
+          );
       tx.outputsData.push(hexFrom(newDidWeb5Data.toBytes()));
     }
   } else {
@@ -141,6 +158,7 @@ async function main(
     let typeScript = script.clone();
     let typeId = hashTypeId(tx.inputs[0], 0);
     typeScript.args = hexFrom(typeId.slice(0, config?.shortArgs ? 10 : 42)); // 20 bytes Type ID
+    typeArgs = typeScript.args;
     codeHashToRun = typeScript.hash();
 
     let count = config?.outputCellCount ?? 1;
@@ -153,13 +171,13 @@ async function main(
   }
 
   // witness
-  if (!config?.noAssociatePlc) {
+  if (!config?.noAssociatePlc && !config?.noWitness) {
     let txHash = tx.hash();
     if (config.invalidSignature) {
       result.rotationKeyIndices.push(0n);
       result.sig = "0x00";
     } else {
-      await plc.signDidWeb5(result, 0, txHash);
+      await plc.signDidWeb5(result, 0, typeArgs, txHash);
     }
     if (!result.sig) {
       throw new Error("Signature is required");
@@ -274,6 +292,100 @@ describe("did-web5-ts", () => {
     await main(result, {}, true);
   });
 
+  test("it should process a V2 genesis operation without associated did:plc correctly", async () => {
+    let result = await plc.generateOperations();
+    await main(result, { noAssociatePlc: true, dataV2: true });
+  });
+  test("it should reject a V2 genesis operation without associated did:plc but with plc_head", async () => {
+    let result = await plc.generateOperations();
+    const plcHead = plc.operationCid(result.history[0]!);
+    await main(result, { noAssociatePlc: true, dataV2: true, plcHead }, true);
+  });
+  test("it should process a V2 genesis operation with plc_head correctly", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    const plcHead = plc.operationCid(result.history.at(-1)!);
+    await main(result, { dataV2: true, plcHead });
+  });
+  test("it should process a V2 genesis operation with associated did:plc and no plc_head", async () => {
+    // allowed, the next update verifies the history from genesis
+    let result = await plc.generateOperations({ moreOps: true });
+    await main(result, { dataV2: true });
+  });
+  test("it should process an update dropping plc_head", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    const prevPlcHead = plc.operationCid(result.history[1]!);
+    await main(plc.fromCheckpoint(result, 1), {
+      update: true,
+      dataV2: true,
+      prevPlcHead,
+    });
+  });
+  test("it should reject a V2 genesis operation with a wrong plc_head", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    // not the last operation of the history
+    const plcHead = plc.operationCid(result.history[0]!);
+    await main(result, { dataV2: true, plcHead }, true);
+  });
+  test("it should process an update with a checkpointed history correctly", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    const prevPlcHead = plc.operationCid(result.history[1]!);
+    const plcHead = plc.operationCid(result.history.at(-1)!);
+    await main(plc.fromCheckpoint(result, 1), {
+      update: true,
+      dataV2: true,
+      prevPlcHead,
+      plcHead,
+    });
+  });
+  test("it should reject an update with a history not starting at the checkpoint", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    const prevPlcHead = plc.operationCid(result.history[1]!);
+    const plcHead = plc.operationCid(result.history.at(-1)!);
+    await main(
+      plc.fromCheckpoint(result, 2),
+      { update: true, dataV2: true, prevPlcHead, plcHead },
+      true,
+    );
+  });
+  test("it should reject an update with a checkpointed history and a wrong plc_head", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    const prevPlcHead = plc.operationCid(result.history[1]!);
+    await main(
+      plc.fromCheckpoint(result, 1),
+      { update: true, dataV2: true, prevPlcHead, plcHead: prevPlcHead },
+      true,
+    );
+  });
+  test("it should process an update replacing a plc_head on a cancelled fork with the recovered history", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    // after the third operation, signed by the second rotation key. The last operation
+    // of the history, signed by the first one, recovers from it.
+    const prevPlcHead = plc.operationCid(await plc.forkOperation(result, 2, 1));
+    const plcHead = plc.operationCid(result.history.at(-1)!);
+    await main(result, { update: true, dataV2: true, prevPlcHead, plcHead });
+  });
+  test("it should process an update without authorization keeping plc_head", async () => {
+    let result = await plc.generateOperations();
+    const plcHead = plc.operationCid(result.history[0]!);
+    await main(result, {
+      update: true,
+      dataV2: true,
+      noWitness: true,
+      prevPlcHead: plcHead,
+      plcHead,
+    });
+  });
+  test("it should reject an update without authorization changing plc_head", async () => {
+    let result = await plc.generateOperations({ moreOps: true });
+    const prevPlcHead = plc.operationCid(result.history[0]!);
+    const plcHead = plc.operationCid(result.history.at(-1)!);
+    await main(
+      result,
+      { update: true, dataV2: true, noWitness: true, prevPlcHead, plcHead },
+      true,
+    );
+  });
+
   test("it should re-create the spec example", async () => {
     let previousTxHash: Hex =
       "0x1ecbf88d692a14d7cbc0bfd1a3d5019e4b613247ae438bad52f94148c6009559";
@@ -489,7 +601,7 @@ describe("did-web5-ts", () => {
     );
     tx.outputsData.push(hexFrom(didWeb5Data.toBytes()));
 
-    await plc.signDidWeb5(migration, 0, tx.hash());
+    await plc.signDidWeb5(migration, 0, typeScript.args, tx.hash());
     expect(migration.sig).toBeTruthy();
     if (!migration.sig) {
       throw new Error("Signature is required");
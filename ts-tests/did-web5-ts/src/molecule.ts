@@ -45,17 +45,67 @@ export class DidWeb5DataV1 extends mol.Entity.Base<
   }
 }
 
+// table DidWeb5DataV2 {
+//     document: Bytes,
+//     localId: StringOpt,
+//     plcHead: BytesOpt,
+// }
+export type DidWeb5DataV2Like = {
+  document: HexLike;
+  localId?: HexLike | null;
+  plcHead?: HexLike | null;
+};
+
+@mol.codec(
+  mol.table({
+    document: mol.Bytes,
+    localId: mol.BytesOpt,
+    plcHead: mol.BytesOpt,
+  }),
+)
+export class DidWeb5DataV2 extends mol.Entity.Base<
+  DidWeb5DataV2Like,
+  DidWeb5DataV2
+>() {
+  constructor(
+    public document: Hex,
+    public localId?: Hex,
+    public plcHead?: Hex,
+  ) {
+    super();
+  }
+
+  static from(data: DidWeb5DataV2Like): DidWeb5DataV2 {
+    if (data instanceof DidWeb5DataV2) {
+      return data;
+    }
+    return new DidWeb5DataV2(
+      hexFrom(data.document),
+      data.localId ? hexFrom(data.localId) : undefined,
+      data.plcHead ? hexFrom(data.plcHead) : undefined,
+    );
+  }
+}
+
 // union DidWeb5Data {
 //   DidWeb5DataV1,
+//   DidWeb5DataV2,
 // }
 
-export type DidWeb5DataLike = {
-  value: DidWeb5DataV1Like;
-};
+export type DidWeb5DataLike =
+  | {
+      type?: "DidWeb5DataV1";
+      value: DidWeb5DataV1Like;
+    }
+  | {
+      type: "DidWeb5DataV2";
+      value: DidWeb5DataV2Like;
+    };
 
 @mol.codec(
   mol.union({
     DidWeb5DataV1,
+    DidWeb5DataV2,
   }),
 )
 export class DidWeb5Data extends mol.Entity.Base<
@@ -63,8 +113,8 @@ export class DidWeb5Data extends mol.Entity.Base<
   DidWeb5Data
 >() {
   constructor(
-    public type: "DidWeb5DataV1",
-    public value: DidWeb5DataV1,
+    public type: "DidWeb5DataV1" | "DidWeb5DataV2",
+    public value: DidWeb5DataV1 | DidWeb5DataV2,
   ) {
     super();
   }
@@ -73,6 +123,9 @@ export class DidWeb5Data extends mol.Entity.Base<
     if (data instanceof DidWeb5Data) {
       return data;
     }
+    if (data.type === "DidWeb5DataV2") {
+      return new DidWeb5Data("DidWeb5DataV2", DidWeb5DataV2.from(data.value));
+    }
     return new DidWeb5Data("DidWeb5DataV1", DidWeb5DataV1.from(data.value));
   }
 }
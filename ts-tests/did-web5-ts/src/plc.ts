@@ -7,6 +7,7 @@ import {
   updateRotationKeysOp,
 } from "@did-plc/lib";
 import * as cbor from "@ipld/dag-cbor";
+import { createHash } from "crypto";
 
 import * as uint8arrays from "uint8arrays";
 import { bytesFrom, Hex, hexFrom, Num, numFrom } from "@ckb-ccc/core";
@@ -105,13 +106,70 @@ export async function generateOperations(config?: {
   };
 }
 
+// CID of an operation as stored in `plcHead`: CIDv1, dag-cbor, sha2-256
+export function operationCid(op: Hex): Hex {
+  const digest = createHash("sha256").update(bytesFrom(op)).digest();
+  return hexFrom(
+    uint8arrays.concat([new Uint8Array([0x01, 0x71, 0x12, 0x20]), digest]),
+  );
+}
+
+// Keeps the history from its operation at `index`, a checkpoint verified before. Only
+// the operations after it are verified, so their rotation key indices are kept only.
+export function fromCheckpoint(
+  result: PlcOperationResult,
+  index: number,
+): PlcOperationResult {
+  return {
+    ...result,
+    history: result.history.slice(index),
+    rotationKeyIndices: result.rotationKeyIndices.slice(index + 1),
+  };
+}
+
+// An operation after the one at `index`, signed by the rotation key at `keyIndex`. When a
+// later operation of the history on the same prev is signed by a higher-priority key, it
+// is cancelled by that recovery.
+export async function forkOperation(
+  result: PlcOperationResult,
+  index: number,
+  keyIndex: number,
+): Promise<Hex> {
+  const prev = cbor.decode(bytesFrom(result.history[index]!)) as Operation;
+  const op = await updateHandleOp(
+    prev,
+    result.keyPairs[keyIndex]!,
+    "at://mallory.example.com",
+  );
+  return hexFrom(cbor.encode(op));
+}
+
+// Same as `authorization_message` of ckb-did-plc-utils. The type ID is the first 20
+// bytes of `typeArgs`, as in the contract.
+export function authorizationMessage(
+  binaryDid: Hex,
+  typeArgs: Hex,
+  txHash: Hex,
+): Uint8Array {
+  const typeId = bytesFrom(typeArgs).slice(0, 20);
+  const text = [
+    "did:web5 local id authorization",
+    "did:plc:" + uint8arrays.toString(bytesFrom(binaryDid), "base32"),
+    "did:web5:" + uint8arrays.toString(typeId, "base32"),
+    "tx hash: " + txHash,
+  ].join("\n");
+  return uint8arrays.fromString(text, "utf8");
+}
+
 export async function signDidWeb5(
   result: PlcOperationResult,
   rotationKeyIndex: number,
-  msg: Hex,
+  typeArgs: Hex,
+  txHash: Hex,
 ): Promise<void> {
   let keypair = result.keyPairs[rotationKeyIndex];
-  let signature = await keypair.sign(bytesFrom(msg));
+  let msg = authorizationMessage(result.binaryDid, typeArgs, txHash);
+  let signature = await keypair.sign(msg);
   result.sig = hexFrom(signature);
   result.rotationKeyIndices.push(numFrom(rotationKeyIndex));
 }